@@ -1,4 +1,7 @@
 use std::convert::TryInto;
+use std::env;
+use intcode::patch::Patch;
+use intcode::search::{Observable, Search, Strategy, Variable};
 // One change is, I'm using Result instead of panicking. That propogates all the
 // way up through main returning Result. If there is an illegal opcode, main
 // will print that error out instead of panicking. Generally, panicking should
@@ -11,38 +14,41 @@ use std::convert::TryInto;
 #[derive(Debug)]
 enum Error {
     IllegalOpcode { code: usize },
+    ComputerError { internal: intcode::Error },
+}
+// The ? operator uses this to convert errors from the shared Intcode crate.
+impl From<intcode::Error> for Error {
+    fn from(internal: intcode::Error) -> Self {
+        Error::ComputerError { internal }
+    }
 }
-const PROGRAM: [usize; 165] = [
-    1, 0, 0, 3, 1, 1, 2, 3, 1, 3, 4, 3, 1, 5, 0, 3, 2, 1, 6, 19, 1, 9, 19, 23, 1, 6, 23, 27, 1, 10,
-    27, 31, 1, 5, 31, 35, 2, 6, 35, 39, 1, 5, 39, 43, 1, 5, 43, 47, 2, 47, 6, 51, 1, 51, 5, 55, 1,
-    13, 55, 59, 2, 9, 59, 63, 1, 5, 63, 67, 2, 67, 9, 71, 1, 5, 71, 75, 2, 10, 75, 79, 1, 6, 79,
-    83, 1, 13, 83, 87, 1, 10, 87, 91, 1, 91, 5, 95, 2, 95, 10, 99, 2, 9, 99, 103, 1, 103, 6, 107,
-    1, 107, 10, 111, 2, 111, 10, 115, 1, 115, 6, 119, 2, 119, 9, 123, 1, 123, 6, 127, 2, 127, 10,
-    131, 1, 131, 6, 135, 2, 6, 135, 139, 1, 139, 5, 143, 1, 9, 143, 147, 1, 13, 147, 151, 1, 2,
-    151, 155, 1, 10, 155, 0, 99, 2, 14, 0, 0,
-];
 fn main() -> Result<(), Error> {
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            // I used a Vec instead of an array for two reasons. First, because
-            // it is flexible about the size of the contained program. Second,
-            // because arrays are on the stack and Vecs are on the heap. Since
-            // this is "big", I think it's better on the heap.
-            //
-            // I was unsure if using vec![with the program here] would create an
-            // array on the stack from which to initialize the Vec, so I moved
-            // the program into the global constant PROGRAM and use to_vec here
-            // to get a fresh memory initialized from that each iteration.
-            let mut mem = PROGRAM.to_vec();
-            mem[1] = noun;
-            mem[2] = verb;
-            if run(mem)? == 19_690_720 {
-                println!("{}", 100 * noun + verb);
-                return Ok(());
-            };
-        }
+    // Part 1 runs the program in the "1202 program alarm" state (see 1202.patch) on the
+    // little computer below.
+    //
+    // I used a Vec instead of an array for two reasons. First, because it is
+    // flexible about the size of the contained program. Second, because arrays
+    // are on the stack and Vecs are on the heap. Since this is "big", I think
+    // it's better on the heap.
+    //
+    // The shared loader reports a bad value by line and column instead of
+    // panicking. Pass another file (text, binary image, gzip) as the argument.
+    let filename = env::args().nth(1).unwrap_or_else(|| "input.txt".to_string());
+    let prog = intcode::program::load(&filename)?;
+    let mut alarm = prog.clone();
+    Patch::load("1202.patch")?.apply(&mut alarm)?;
+    let mem = alarm.iter().map(|&val| val as usize).collect();
+    println!("Part 1: {}", run(mem)?);
+    // Part 2 searches over noun and verb, two memory addresses, with the shared
+    // Intcode crate, which tries all 10,000 combinations spread across every core.
+    let search = Search::new(prog, vec![
+        Variable::Memory { addr: 1, range: 0..=99 }, // noun
+        Variable::Memory { addr: 2, range: 0..=99 }, // verb
+    ]);
+    match search.solve_for(Observable::Memory(0), 19_690_720, Strategy::BruteForce)? {
+        Some(answer) => println!("Part 2: {}", 100 * answer[0] + answer[1]),
+        None => println!("No answer."),
     }
-    println!("No answer.");
     Ok(())
 }
 fn run(mut mem: Vec<usize>) -> Result<usize, Error> {
@@ -1,17 +1,12 @@
-use std::convert::TryInto;
+use std::iter;
+use intcode::{Error, Intcode};
+use intcode::io::{InputIter, OutputFn};
 // One change is, I'm using Result instead of panicking. That propogates all the
 // way up through main returning Result. If there is an illegal opcode, main
 // will print that error out instead of panicking. Generally, panicking should
 // be only due to bugs and not due to input data value.
-// For the error type, I'm using an enum. I derive Debug on it so that if there
-// is an illegal opcode, it gets printed nicely. That's also the reason that I
-// used a struct-like declaration for the IllegalOpcode parameter, instead of a
-// tuple-like declaration, so that the automatically-generated Debug error
-// message is more helpful.
-#[derive(Debug)]
-enum Error {
-    IllegalOpcode { code: isize },
-}
+// The error type is the shared computer's, which derives Debug so that if there
+// is an illegal opcode, it gets printed nicely.
 // const PROGRAM: [isize; 8] = [1002,4,3,4,33,0,0,0];
 const PROGRAM: [isize; 165] = [
     1, 0, 0, 3, 1, 1, 2, 3, 1, 3, 4, 3, 1, 5, 0, 3, 2, 1, 6, 19, 1, 9, 19, 23, 1, 6, 23, 27, 1, 10,
@@ -46,109 +41,11 @@ fn main() -> Result<(), Error> {
     println!("No answer.");
     Ok(())
 }
-fn run(mut mem: Vec<isize>) -> Result<isize, Error> {
-    // Instead of mutating a pc variable with increment, I step the pc by 4
-    // through the program...
-    for pc in (0..mem.len()).step_by(4) {
-        // ...and then take a 4-element slice from the memory, which is
-        // converted into an array by TryFrom. That can't fail, so I unwrap the
-        // result.
-        let instruction: [isize; 4] = (&mem[pc..pc + 4]).try_into().unwrap();
-        // I am implementing TryFrom instead of From because conversion from a
-        // numeric value to an Opcode can fail. (Note that implementing TryFrom
-        // implicitly implements TryInto.) I am using the '?' operator to bail
-        // early with an error if the conversion fails.
-        let opcode: Opcode = instruction.try_into()?;
-        // I moved execution logic for the opcodes to their own impl. The return
-        // value is whether or not the instruction executed. Only Halt returns false.
-        if !opcode.execute(&mut mem, &input_1, &output) {
-            break;
-        }
-    }
-    Ok(mem[0])
-}
-fn input_1() -> isize {1}
-fn output(val: isize) {
-    println!("Program Output: {}", val);
-}
-enum OpcodeMode {
-    Indirect,
-    Direct,
-}
-// For Add and Multiply, I store the operands (which are addresses) within. I
-// use an array because the pattern match on an array is exhaustive, since an
-// array has a fixed size. Slices on the other hand, because they have unknown
-// size, don't have exhaustive pattern matching for a known-length slice.
-enum Opcode {
-    Add([OpcodeMode; 3],[isize; 3]), // 2 operands and a result (always an address)
-    Multiply([OpcodeMode; 3],[isize; 3]), // 2 operands and a result (always an address)
-    Input([OpcodeMode; 1],[isize;1]), // address (always) to store machine input
-    Output([OpcodeMode; 1],[isize;1]), // address (always) to store machine output
-    Halt,
-}
-impl Opcode {
-    fn execute(self, mem: &mut [isize],
-                     input: &dyn Fn()->isize,
-                     output: &dyn Fn(isize)->()) -> bool {
-        match self {
-            // Here you can see the nice pattern match on the operands.
-            Opcode::Add([mode_a, mode_b, _mode_c],[a, b, c]) => {
-                mem[c as usize]
-                = match mode_a {OpcodeMode::Indirect=>mem[a as usize], OpcodeMode::Direct=>a}
-                + match mode_b {OpcodeMode::Indirect=>mem[b as usize], OpcodeMode::Direct=>b};
-            },
-            Opcode::Multiply([mode_a, mode_b, _mode_c],[a, b, c]) => {
-                mem[c as usize]
-                = match mode_a {OpcodeMode::Indirect=>mem[a as usize], OpcodeMode::Direct=>a}
-                * match mode_b {OpcodeMode::Indirect=>mem[b as usize], OpcodeMode::Direct=>b};
-            },
-            Opcode::Input([_mode_a],[a]) => mem[a as usize] = input(),
-            Opcode::Output([mode_a],[a]) => {
-                output(match mode_a {OpcodeMode::Indirect=>mem[a as usize], OpcodeMode::Direct=>a});
-            },
-            Opcode::Halt => return false,
-        }
-        true
-    }
-}
-impl std::convert::TryFrom<[isize; 4]> for Opcode {
-    type Error = Error;
-    fn try_from([fullcode, a, b, c]: [isize; 4]) -> Result<Self, Self::Error> {
-        let code = fullcode - fullcode/100*100; // two digits on right
-        let (mode_a,mode_b,mode_c) = Self::parsemode3(fullcode/100)?;
-        let opcode = match code {
-            1 => Opcode::Add([mode_a, mode_b, mode_c],[a, b, c]),
-            2 => Opcode::Multiply([mode_a, mode_b, mode_c],[a, b, c]),
-            3 => Opcode::Input([mode_a],[a]),
-            4 => Opcode::Output([mode_a],[a]),
-            99 => Opcode::Halt,
-            _ => return Err(Error::IllegalOpcode { code }),
-        };
-        Ok(opcode)
-    }
-}
-impl Opcode {
-    fn len(&self) -> usize {
-        match self {
-            Opcode::Add(_mode_array, param_array) => param_array.len(),
-            _ => panic!("Not Implemented Yet"),
-        }
-    }
-    fn parsemode3(d: isize)  -> Result<(OpcodeMode, OpcodeMode, OpcodeMode), Error> {
-        let mode_a = match d - d/10*10 {0=>OpcodeMode::Indirect,1=>OpcodeMode::Direct,_=>{return Err(Error::IllegalOpcode{code: d*100});},};
-        let mode_b = match d - d/10*10 {0=>OpcodeMode::Indirect,1=>OpcodeMode::Direct,_=>{return Err(Error::IllegalOpcode{code: d*100});},};
-        let mode_c = match d - d/10*10 {0=>OpcodeMode::Indirect,1=>OpcodeMode::Direct,_=>{return Err(Error::IllegalOpcode{code: d*100});},};
-        Ok((mode_a, mode_b, mode_c))
-    }
-}
-
-#[test]
-fn test_add() {
-    let op = Opcode::Add(1,2);
-    assert_eq!(op.execute(), 3);
+fn run(mem: Vec<isize>) -> Result<isize, Error> {
+    // The shared computer answers every input with 1 and prints every output.
+    let input = InputIter(iter::repeat(1));
+    let output = OutputFn(|val| println!("Program Output: {}", val));
+    let mut computer = Intcode::with_io(mem, input, output);
+    computer.run_blocking()?;
+    Ok(computer.memory()[0])
 }
-#[test]
-fn test_mult() {
-    let op = Opcode::Mult(1,2);
-    assert_eq!(op.execute(), 2);
-}
\ No newline at end of file
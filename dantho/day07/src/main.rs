@@ -1,7 +1,4 @@
 /// Day03 code stolen from https://github.com/kodsnack/advent_of_code_2019/blob/master/tomasskare-rust/day2/src/main.rs
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 use futures::prelude::*;
 use futures::channel::mpsc::{channel,Sender,Receiver};
 use futures::executor::block_on;
@@ -153,15 +150,7 @@ fn main() {
     // let filename = "day07_example3.txt";
     // let filename = "day07_example4.txt";
     // let filename = "day07_example5.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let prog_orig = intcode::program::load(filename).unwrap_or_else(|e| panic!("Failure loading {}: {:?}", filename, e));
     let initial_input = 0;
     let mut max_out = std::isize::MIN;
     let phases0 = vec![9,8,7,6,5];
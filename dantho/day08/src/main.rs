@@ -1,79 +1,62 @@
 /// https://adventofcode.com/2019/day/8
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::collections::HashMap;
+///   cargo run -- [--input input.txt] [--size 25x6] [--blocks] [--pbm decoded.pbm]
+///   cargo run -- --encode picture.pbm [--layers 3]   # a PBM/PGM as SIF digits
+mod sif;
 
-fn main() {
-    let (filename, cols, rows) = ("input.txt", 25, 6);
-    // let (filename, cols, rows) = ("day08_example.txt", 3, 2);
-    // let filename = "day08_example1.txt"; 
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let input = buf.lines().next().expect(&format!("Input error reading file '{}'", filename)).ok().unwrap();
-    let mut digits = input.chars().map(|d| {
-        let num = d.to_string().parse::<u8>();
-        num.ok().expect(&format!("Illegal char in input: '{}'", d))
-    });
-    let mut all_layers: Vec<Vec<Vec<u8>>> = Vec::new();
-    'layers: for layer in 1.. {
-        let mut new_layer: Vec<Vec<u8>> = Vec::new();
-        for row in 0..rows {
-            let mut new_row: Vec<u8> = Vec::new();
-            for col in 0..cols {
-                match digits.next() {
-                    Some(d) => new_row.push(d),
-                    None => {
-                        if col == 0 && row == 0 {
-                            break 'layers;
-                        } else {
-                            panic!("Input stopped in middle of ({} x {}) layer #{}.", cols, rows, layer);
-                        }
-                    }
-                };
-            }
-            new_layer.push(new_row);
+use std::fs;
+use render::{Font, RunConfig};
+use sif::{Ascii, Blocks, Error, Palette, SpaceImage};
+
+fn main() -> Result<(),Error> {
+    let (mut filename, mut cols, mut rows) = ("input.txt".to_string(), 25, 6);
+    let (mut encode, mut layers) = (None, 1);
+    let (mut palette, mut pbm): (&dyn Palette, _) = (&Ascii, None);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let bad = |msg: String| Error::BadOption { msg };
+        let mut value = |option: &str| args.next().ok_or_else(|| bad(format!("{} needs a value", option)));
+        match arg.as_str() {
+            "--input" => filename = value("--input")?,
+            "--size" => {
+                let size = value("--size")?;
+                let dims = size.split_once('x').and_then(|(c, r)| Some((c.parse().ok()?, r.parse().ok()?)));
+                (cols, rows) = dims.ok_or_else(|| bad(format!("--size is <cols>x<rows>, not {}", size)))?;
+            },
+            "--blocks" => palette = &Blocks,
+            "--pbm" => pbm = Some(value("--pbm")?),
+            "--encode" => encode = Some(value("--encode")?),
+            "--layers" => {
+                let count = value("--layers")?;
+                layers = count.parse().map_err(|_| bad(format!("--layers needs a number, not {}", count)))?;
+            },
+            _ => (), // RunConfig's
         }
-        all_layers.push(new_layer);
     }
-    println!("Found {} layers, each is {} cols by {} rows.",
-        all_layers.len(), all_layers[0][0].len(), all_layers[0].len());
+    if let Some(filename) = encode {
+        let picture = fs::read(&filename).unwrap_or_else(|_| panic!("Failure opening {}", filename));
+        let image = SpaceImage::from_pnm(&picture, layers)?;
+        eprintln!("{} layers, each is {} cols by {} rows.", image.layers().len(), image.width(), image.height());
+        println!("{}", image.to_sif());
+        return Ok(());
+    }
+    let input = fs::read_to_string(&filename).unwrap_or_else(|_| panic!("Failure opening {}", filename));
+    let image = SpaceImage::parse(&input, cols, rows)?;
+    println!("Found {} layers, each is {} cols by {} rows.", image.layers().len(), image.width(), image.height());
 
     // find count of 1's and 2's in layer with fewest zeros
-    let (_layer_num, _cnt_0s, cnt_1s, cnt_2s) = all_layers.iter().fold((0,std::u8::MAX,0,0), |(layer_num, cnt_0s, cnt_1s, cnt_2s), layer| {
-        let mut digihash = HashMap::new();
-        layer.iter().flatten().for_each(|d| {
-            *digihash.entry(d).or_default() += 1;
-        });
-        let zeros = if let Some(cnt) = digihash.get(&0) {*cnt} else {0};
-        // Decide what to return (fold), old layer or this new one
-        let layer_num = layer_num + 1;
-        if zeros < cnt_0s {
-            let ones = if let Some(cnt) = digihash.get(&1) {*cnt} else {0};
-            let twos = if let Some(cnt) = digihash.get(&2) {*cnt} else {0};
-            (layer_num, zeros, ones, twos)
-        } else {
-            (layer_num,cnt_0s,cnt_1s,cnt_2s)
-        }
-    });
-    println!("Part 1 answer is {}.", cnt_1s as i32 * cnt_2s as i32);
+    let (_layer_num, checksum) = image.checksum();
+    println!("Part 1 answer is {}.", checksum);
 
-    // Process image: 2=transparent (ignore), 1=white, 0=black
-    let transparent_flat_layer = vec![2u8;rows*cols];
-    let processed_flat_layer = all_layers.into_iter()
-    .fold(transparent_flat_layer, |upper, lower| {
-        upper.into_iter().zip(lower.into_iter().flatten())
-        .map(|(u,l)|{
-            if u == 2 {l} else {u}
-        }).collect()
-    });
-    // Print processed image
-    processed_flat_layer.iter()
-    .map(|d|{if *d == 1 {'#'} else {' '}})
-    .enumerate()
-    .for_each(|(num,c)|{
-        if 0 == num % cols { println!(""); }
-        print!("{}", c);
-    })
-    
+    // Print processed image, and save it as a picture if asked
+    print!("\n{}", image.render(palette));
+    let picture = image.render(&Ascii);
+    RunConfig::from_args().save_image(&picture);
+    if let Some(filename) = pbm {
+        fs::write(&filename, image.to_pbm()).unwrap_or_else(|_| panic!("Failure writing {}", filename));
+    }
+    match Font::standard().read(&picture) {
+        Ok(letters) => println!("Part 2 answer is {}.", letters),
+        Err(e) => println!("Part 2: {}", e),
+    }
+    Ok(())
 }
@@ -0,0 +1,261 @@
+//! The Space Image Format: a picture as layers of digits, width x height each, stacked so the
+//! first layer is in front.  0 is black, 1 white, 2 transparent (shows what's behind).
+
+pub const BLACK: u8 = 0;
+pub const WHITE: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+#[derive(Debug,PartialEq)]
+pub enum Error {
+    BadSize { width: usize, height: usize },
+    NoLayers,
+    IllegalDigit { ch: char, at: usize },
+    PartialLayer { layer: usize, digits: usize }, // input stopped `digits` into this (1 based) layer
+    BadPnm { msg: String },
+    BadOption { msg: String },
+}
+
+/// How each color is shown
+pub trait Palette {
+    fn glyph(&self, color: u8) -> char;
+}
+/// '#' for white, blank otherwise -- what `render::Font` reads
+pub struct Ascii;
+impl Palette for Ascii {
+    fn glyph(&self, color: u8) -> char {
+        if color == WHITE {'#'} else {' '}
+    }
+}
+/// Solid blocks for white, shading where the whole stack is transparent
+pub struct Blocks;
+impl Palette for Blocks {
+    fn glyph(&self, color: u8) -> char {
+        match color {
+            WHITE => '█',
+            TRANSPARENT => '░',
+            _ => ' ',
+        }
+    }
+}
+impl<F: Fn(u8) -> char> Palette for F {
+    fn glyph(&self, color: u8) -> char {
+        self(color)
+    }
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u8>>, // front first, each row by row
+}
+impl SpaceImage {
+    pub fn parse(text: &str, width: usize, height: usize) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::BadSize { width, height });
+        }
+        let size = width.checked_mul(height).ok_or(Error::BadSize { width, height })?;
+        let text = text.trim_end();
+        let digits = text.chars().enumerate().map(|(at, ch)| {
+            ch.to_digit(10).map(|d| d as u8).ok_or(Error::IllegalDigit { ch, at })
+        }).collect::<Result<Vec<u8>, Error>>()?;
+        if digits.is_empty() {
+            return Err(Error::NoLayers);
+        }
+        if digits.len() % size != 0 {
+            return Err(Error::PartialLayer { layer: digits.len() / size + 1, digits: digits.len() % size });
+        }
+        let layers = digits.chunks(size).map(|layer| layer.to_vec()).collect();
+        Ok(SpaceImage { width, height, layers })
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn layers(&self) -> &[Vec<u8>] {
+        &self.layers
+    }
+    /// How many of each digit every layer holds
+    pub fn histograms(&self) -> Vec<[usize; 10]> {
+        self.layers.iter().map(|layer| {
+            let mut counts = [0; 10];
+            layer.iter().for_each(|d| counts[*d as usize] += 1);
+            counts
+        }).collect()
+    }
+    /// The layer with the fewest 0s (the first, if tied) and its count of 1s times its count of 2s
+    pub fn checksum(&self) -> (usize, usize) {
+        let (layer, counts) = self.histograms().into_iter().enumerate()
+            .min_by_key(|(_, counts)| counts[0])
+            .expect("SpaceImage always has a layer");
+        (layer, counts[1] * counts[2])
+    }
+    /// Each pixel's color is its front-most that isn't transparent
+    pub fn composite(&self) -> Vec<u8> {
+        let transparent = vec![TRANSPARENT; self.width * self.height];
+        self.layers.iter().fold(transparent, |front, behind| {
+            front.into_iter().zip(behind).map(|(f, b)| if f == TRANSPARENT {*b} else {f}).collect()
+        })
+    }
+    /// The composite, a line per row
+    pub fn render(&self, palette: &dyn Palette) -> String {
+        self.composite().chunks(self.width)
+            .map(|row| row.iter().map(|color| palette.glyph(*color)).collect::<String>() + "\n")
+            .collect()
+    }
+    pub fn to_sif(&self) -> String {
+        self.layers.iter().flatten().map(|d| char::from(b'0' + d)).collect()
+    }
+    /// The composite as a plain PBM, transparent showing as white
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", self.width, self.height);
+        for row in self.composite().chunks(self.width) {
+            let bits: Vec<_> = row.iter().map(|color| if *color == BLACK {"1"} else {"0"}).collect();
+            pbm += &(bits.join(" ") + "\n");
+        }
+        pbm
+    }
+    /// A PBM or PGM (plain or raw) picture, dealt out over `layers` layers: pixel i is in layer
+    /// i % layers, transparent in the rest.  Grays are white from half brightness up.
+    pub fn from_pnm(data: &[u8], layers: usize) -> Result<Self, Error> {
+        let mut pnm = Pnm { data, at: 0, token_end: 0 };
+        let magic = pnm.token()?;
+        let width = pnm.number()?;
+        let height = pnm.number()?;
+        let maxval = if magic == "P1" || magic == "P4" {1} else {pnm.number()?};
+        if !(1..=65535).contains(&maxval) {
+            return Err(Error::BadPnm { msg: format!("Maximum gray {} isn't 1 to 65535", maxval) });
+        }
+        if width == 0 || height == 0 {
+            return Err(Error::BadSize { width, height });
+        }
+        // Sizes from the header can be anything, so the arithmetic on them is checked
+        let size = width.checked_mul(height).ok_or(Error::BadSize { width, height })?;
+        let colors: Vec<u8> = match magic.as_str() {
+            "P1" => (0..size).map(|_| pnm.bit()).collect::<Result<_, _>>()?,
+            "P2" => (0..size).map(|_| pnm.number().and_then(|v| pnm.gray(v, maxval))).collect::<Result<_, _>>()?,
+            "P4" => {
+                let len = height.checked_mul(width.div_ceil(8)).ok_or(Error::BadSize { width, height })?;
+                let raster = pnm.raster(len)?;
+                raster.chunks(width.div_ceil(8)).flat_map(|row| {
+                    (0..width).map(move |x| (row[x / 8] >> (7 - x % 8)) & 1)
+                }).map(|bit| 1 - bit as usize).collect::<Vec<_>>()
+            },
+            "P5" => {
+                let bytes = if maxval < 256 {1} else {2};
+                let len = size.checked_mul(bytes).ok_or(Error::BadSize { width, height })?;
+                let raster = pnm.raster(len)?;
+                raster.chunks(bytes)
+                    .map(|v| pnm.gray(v.iter().fold(0, |value, byte| value << 8 | *byte as usize), maxval))
+                    .collect::<Result<_, _>>()?
+            },
+            _ => return Err(Error::BadPnm { msg: format!("'{}' isn't a PBM or PGM", magic) }),
+        }.into_iter().map(|white: usize| if white > 0 {WHITE} else {BLACK}).collect();
+        let layers = layers.max(1);
+        let layers = (0..layers).map(|layer| {
+            colors.iter().enumerate()
+                .map(|(i, color)| if i % layers == layer {*color} else {TRANSPARENT})
+                .collect()
+        }).collect();
+        Ok(SpaceImage { width, height, layers })
+    }
+}
+
+/// Reading through a PNM's header, comments and all
+struct Pnm<'a> {
+    data: &'a [u8],
+    at: usize,
+    token_end: usize, // just past the last token read
+}
+impl<'a> Pnm<'a> {
+    fn skip_space(&mut self) {
+        while let Some(byte) = self.data.get(self.at) {
+            match byte {
+                b'#' => while self.data.get(self.at).is_some_and(|b| *b != b'\n') { self.at += 1 },
+                b if b.is_ascii_whitespace() => self.at += 1,
+                _ => break,
+            }
+        }
+    }
+    fn token(&mut self) -> Result<String, Error> {
+        self.skip_space();
+        let start = self.at;
+        while self.data.get(self.at).is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#') {
+            self.at += 1;
+        }
+        if start == self.at {
+            return Err(Error::BadPnm { msg: format!("Ended at byte {}, expecting more", start) });
+        }
+        self.token_end = self.at;
+        Ok(String::from_utf8_lossy(&self.data[start..self.at]).into_owned())
+    }
+    fn number(&mut self) -> Result<usize, Error> {
+        let token = self.token()?;
+        token.parse().map_err(|_| Error::BadPnm { msg: format!("'{}' isn't a number", token) })
+    }
+    /// A plain PBM pixel, 1 for white; they needn't be spaced apart
+    fn bit(&mut self) -> Result<usize, Error> {
+        self.skip_space();
+        let bit = match self.data.get(self.at) {
+            Some(b'0') => 1,
+            Some(b'1') => 0,
+            _ => return Err(Error::BadPnm { msg: format!("Expecting a 0 or 1 at byte {}", self.at) }),
+        };
+        self.at += 1;
+        Ok(bit)
+    }
+    /// 1 for a gray at least half of `maxval`: white
+    fn gray(&self, value: usize, maxval: usize) -> Result<usize, Error> {
+        if value > maxval {
+            return Err(Error::BadPnm { msg: format!("Gray {} is above the maximum, {}", value, maxval) });
+        }
+        Ok((value * 2 > maxval) as usize)
+    }
+    /// The binary pixels, after the single whitespace ending the header's last token
+    fn raster(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if !self.data.get(self.token_end).is_some_and(|b| b.is_ascii_whitespace()) {
+            return Err(Error::BadPnm { msg: format!("Expecting whitespace at byte {}, ending the header", self.token_end) });
+        }
+        let start = self.token_end + 1;
+        start.checked_add(len).and_then(|end| self.data.get(start..end))
+            .ok_or(Error::BadPnm { msg: format!("Expecting {} bytes of pixels", len) })
+    }
+}
+
+#[test]
+fn test_space_image() {
+    let example = SpaceImage::parse("123456789012\n", 3, 2).unwrap();
+    assert_eq!(example.layers(), [vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]);
+    assert_eq!(example.histograms()[1], [1, 1, 1, 0, 0, 0, 0, 1, 1, 1]);
+    assert_eq!(example.checksum(), (0, 1));
+    assert_eq!(SpaceImage::parse("1234567", 3, 2), Err(Error::PartialLayer { layer: 2, digits: 1 }));
+    assert_eq!(SpaceImage::parse("12x456", 3, 2), Err(Error::IllegalDigit { ch: 'x', at: 2 }));
+    assert_eq!(SpaceImage::parse("", 3, 2), Err(Error::NoLayers));
+
+    let stacked = SpaceImage::parse("0222112222120000", 2, 2).unwrap();
+    assert_eq!(stacked.composite(), [0, 1, 1, 0]);
+    assert_eq!(stacked.render(&Ascii), " #\n# \n");
+    assert_eq!(stacked.render(&|color| char::from(b'a' + color)), "ab\nba\n");
+
+    // PBM -> SIF -> PBM, and the same picture as PGMs
+    let pbm = "P1\n# a checkerboard\n3 2\n1 0 1\n0 1 0\n";
+    let encoded = SpaceImage::from_pnm(pbm.as_bytes(), 4).unwrap();
+    assert_eq!(encoded.layers().len(), 4);
+    let decoded = SpaceImage::parse(&encoded.to_sif(), 3, 2).unwrap();
+    assert_eq!(decoded.to_pbm(), "P1\n3 2\n1 0 1\n0 1 0\n");
+    assert_eq!(SpaceImage::from_pnm(b"P4 3 2\n\xa0\x40", 1).unwrap().to_pbm(), decoded.to_pbm());
+    assert_eq!(SpaceImage::from_pnm(b"P2 3 2 15 0 15 3 9 7 8", 2).unwrap().composite(), decoded.composite());
+    assert_eq!(SpaceImage::from_pnm(b"P5 3 2 255\n\x00\xff\x10\xc0\x7f\x80", 3).unwrap().composite(), decoded.composite());
+    assert!(SpaceImage::from_pnm(b"P6 3 2 255\n", 1).is_err());
+    assert!(SpaceImage::from_pnm(b"P2 1 1 18446744073709551615 5", 1).is_err());
+    assert!(SpaceImage::from_pnm(b"P2 1 1 3 18446744073709551615", 1).is_err());
+    assert!(SpaceImage::from_pnm(b"P5 1 1 0\n\x00", 1).is_err());
+    // The header's last token ends in exactly one whitespace byte, not a comment
+    assert!(SpaceImage::from_pnm(b"P5 3 2 255#\n\x00\xff\x10\xc0\x7f\x80", 1).is_err());
+    let huge = usize::MAX / 2;
+    assert_eq!(SpaceImage::from_pnm(format!("P4 {} 16 ", huge).as_bytes(), 1), Err(Error::BadSize { width: huge, height: 16 }));
+    assert_eq!(SpaceImage::from_pnm(format!("P5 {} 2 65535 ", huge).as_bytes(), 1), Err(Error::BadSize { width: huge, height: 2 }));
+    assert_eq!(SpaceImage::parse("0", huge, 3), Err(Error::BadSize { width: huge, height: 3 }));
+}
@@ -1,17 +1,16 @@
 /// Day03 code stolen from https://github.com/kodsnack/advent_of_code_2019/blob/master/tomasskare-rust/day2/src/main.rs
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use futures::prelude::*;
 use futures::channel::mpsc::{channel,Sender,Receiver};
 use futures::executor::block_on;
 use futures::join;
+use futures::future::BoxFuture;
 use PaintColor::*;
 use TurnDirection::*;
 use Orientation::*;
+use render::{Font, RunConfig};
 
 #[derive(Debug)]
 enum Error {
@@ -20,6 +19,14 @@ enum Error {
     IllegalTurnDirection { val: isize },
     RobotComms { msg: String },
     ComputerComms { msg: String },
+    ProgramLoad { internal: intcode::Error },
+    BadScript { msg: String },
+    Usage { msg: String },
+}
+impl From<intcode::Error> for Error {
+    fn from(internal: intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 #[derive(Debug)]
 enum OpCode {
@@ -157,7 +164,7 @@ async fn intcode_run(mut v: Vec<isize>, mut input: Receiver<isize>, mut output:
     }
     Ok(input) // Drop the input Receiver, this allow downstream fetching of values we are not going to process ('cause we're done)
 }
-#[derive(Debug,Copy,Clone)]
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum PaintColor {
     Black = 0,
     White = 1,
@@ -174,7 +181,7 @@ impl TryFrom<isize> for PaintColor {
         Ok(color)
     }
 }
-#[derive(Debug,Copy,Clone)]
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum TurnDirection {
     Left = 0,
     Right = 1,
@@ -233,81 +240,236 @@ impl Orientation {
         (x,y)
     }
 }
-async fn robot_run(mut rx: Receiver<isize>, mut tx: Sender<isize>) -> Result<HashMap<(isize,isize),PaintColor>,Error> {
-    let mut paint_map = HashMap::new(); // map of paint colors by coords
+/// What drives the robot: shown the color of the panel it's over, it says what to paint there
+/// and which way to turn before stepping on, or None once it's done
+trait Brain: Send {
+    fn think<'a>(&'a mut self, color: PaintColor) -> BoxFuture<'a, Result<Option<(PaintColor,TurnDirection)>,Error>>;
+}
+/// The Intcode program, running at the other end of the channels
+struct IntcodeBrain {
+    rx: Receiver<isize>,
+    tx: Sender<isize>,
+}
+impl Brain for IntcodeBrain {
+    fn think<'a>(&'a mut self, color: PaintColor) -> BoxFuture<'a, Result<Option<(PaintColor,TurnDirection)>,Error>> {
+        async move {
+            if let Err(_) = self.tx.send(color as isize).await {
+                return Err(Error::RobotComms { msg:format!("Robot output channel failure.  The following data is being discarded:\n   {:?}", color) });
+            }
+            let paint = match self.rx.next().await {
+                Some(color_v) => PaintColor::try_from(color_v)?,
+                None => return Ok(None),
+            };
+            let turn = match self.rx.next().await {
+                Some(turn_v) => TurnDirection::try_from(turn_v)?,
+                None => return Ok(None),
+            };
+            Ok(Some((paint, turn)))
+        }.boxed()
+    }
+}
+/// Langton's ant, for `steps` steps: turn right off white, left off black, flipping the color
+struct LangtonsAnt {
+    steps: usize,
+}
+impl Brain for LangtonsAnt {
+    fn think<'a>(&'a mut self, color: PaintColor) -> BoxFuture<'a, Result<Option<(PaintColor,TurnDirection)>,Error>> {
+        async move {
+            if self.steps == 0 {
+                return Ok(None);
+            }
+            self.steps -= 1;
+            Ok(Some(match color {
+                White => (Black, Right),
+                Black => (White, Left),
+            }))
+        }.boxed()
+    }
+}
+/// The same moves whatever it's shown
+struct ScriptedBrain {
+    moves: VecDeque<(PaintColor,TurnDirection)>,
+}
+impl ScriptedBrain {
+    /// Paint and turn values as the Intcode program would output them: "1,0,0,0"
+    fn parse(script: &str) -> Result<Self, Error> {
+        let values = script.split(',').map(|v| {
+            v.trim().parse().map_err(|_| Error::BadScript { msg: format!("'{}' isn't a number", v.trim()) })
+        }).collect::<Result<Vec<isize>,Error>>()?;
+        if values.len() % 2 != 0 {
+            return Err(Error::BadScript { msg: format!("{} values, but each move is a paint and a turn", values.len()) });
+        }
+        let moves = values.chunks(2).map(|pair| {
+            Ok((PaintColor::try_from(pair[0])?, TurnDirection::try_from(pair[1])?))
+        }).collect::<Result<_,Error>>()?;
+        Ok(ScriptedBrain { moves })
+    }
+}
+impl Brain for ScriptedBrain {
+    fn think<'a>(&'a mut self, _color: PaintColor) -> BoxFuture<'a, Result<Option<(PaintColor,TurnDirection)>,Error>> {
+        async move { Ok(self.moves.pop_front()) }.boxed()
+    }
+}
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+struct Bounds {
+    min_x: isize,
+    min_y: isize,
+    max_x: isize,
+    max_y: isize,
+}
+impl Bounds {
+    fn include(&self, (x,y): (isize,isize)) -> Bounds {
+        Bounds { min_x: self.min_x.min(x), min_y: self.min_y.min(y), max_x: self.max_x.max(x), max_y: self.max_y.max(y) }
+    }
+}
+/// The hull after the robot's done, and how it got that way
+struct Hull {
+    panels: HashMap<(isize,isize),PaintColor>, // every panel the robot stood on
+    paint_counts: HashMap<(isize,isize),usize>, // times each was painted
+    bounds: Vec<Bounds>, // around where the robot's been, after each move
+}
+impl Hull {
+    fn painted(&self) -> usize {
+        self.paint_counts.len()
+    }
+    fn painted_more_than_once(&self) -> usize {
+        self.paint_counts.values().filter(|count| **count > 1).count()
+    }
+    /// The move after which the robot last went somewhere new, and how far it had been
+    fn last_growth(&self) -> (usize, Bounds) {
+        let last = self.bounds.iter().enumerate().rev()
+            .find(|(step, bounds)| *step == 0 || self.bounds[step - 1] != **bounds)
+            .map(|(step, bounds)| (step, *bounds));
+        last.unwrap_or((0, Bounds { min_x: 0, min_y: 0, max_x: 0, max_y: 0 }))
+    }
+    /// White panels as '#'
+    fn picture(&self) -> String {
+        let bounds = self.bounds.last().copied().unwrap_or(Bounds { min_x: 0, min_y: 0, max_x: 0, max_y: 0 });
+        let mut painting = String::new();
+        for y in (bounds.min_y..=bounds.max_y).rev() {
+            for x in bounds.min_x..=bounds.max_x {
+                painting.push(match self.panels.get(&(x,y)) {
+                    Some(White) => '#',
+                    _ => ' ',
+                });
+            }
+            painting.push('\n');
+        }
+        painting
+    }
+}
+async fn robot_run(brain: &mut dyn Brain, start: PaintColor) -> Result<Hull,Error> {
+    let mut hull = Hull { panels: HashMap::new(), paint_counts: HashMap::new(), bounds: Vec::new() };
     let mut robot_location = (0,0); // starting location (arbitrary)
     let mut robot_orientation = North; // initial orientation
-    paint_map.entry(robot_location).or_insert(White); // Initialize starting location color;
+    let mut bounds = Bounds { min_x: 0, min_y: 0, max_x: 0, max_y: 0 };
+    hull.panels.insert(robot_location, start); // Initialize starting location color;
     // Now process all messages
     loop {
-        let this_panel_color = paint_map.entry(robot_location).or_insert(Black);
-        if let Err(_) = tx.send(*this_panel_color as isize).await {
-            return Err(Error::RobotComms { msg:format!("Robot output channel failure.  The following data is being discarded:\n   {:?}", this_panel_color) });
-        }
-        if let Some(color_v) = rx.next().await {
-            *this_panel_color = PaintColor::try_from(color_v)?;
-            // println!("Robot: Painting {:?}", *this_panel_color);
-        } else { break; }
-        if let Some(turn_v) = rx.next().await {
-            robot_orientation = robot_orientation.turn(TurnDirection::try_from(turn_v)?);
-            robot_location = robot_orientation.step(robot_location);
-            // println!("Robot: At {:?} facing {:?}", robot_location, robot_orientation);
-        } else { break; }
+        let this_panel_color = hull.panels.entry(robot_location).or_insert(Black);
+        let (paint, turn) = match brain.think(*this_panel_color).await? {
+            Some(thought) => thought,
+            None => break,
+        };
+        *this_panel_color = paint;
+        *hull.paint_counts.entry(robot_location).or_insert(0) += 1;
+        robot_orientation = robot_orientation.turn(turn);
+        robot_location = robot_orientation.step(robot_location);
+        bounds = bounds.include(robot_location);
+        hull.bounds.push(bounds);
     }
-    Ok(paint_map)
+    Ok(hull)
 }
-async fn boot_intcode_and_robot(prog: Vec<isize>) -> Result<HashMap<(isize,isize),PaintColor>,Error> {
+async fn boot_intcode_and_robot(prog: Vec<isize>, start: PaintColor) -> Result<Hull,Error> {
     const BUFFER_SIZE: usize = 100;
     let (robot_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, robot_rx) = channel::<isize>(BUFFER_SIZE);
     let computer = intcode_run(prog.clone(), computer_rx, computer_tx);
-    let robot = robot_run(robot_rx, robot_tx);
+    let mut brain = IntcodeBrain { rx: robot_rx, tx: robot_tx };
+    let robot = robot_run(&mut brain, start);
     let (_computer_return,robot_return) = join!(computer, robot); // , computer_snooper.monitor(), robot_snooper.monitor()
     robot_return
 }
+fn report(hull: &Hull) {
+    let (step, bounds) = hull.last_growth();
+    println!("   {} panels painted more than once; the robot last went further afield on move {}, to x {}..={}, y {}..={}",
+        hull.painted_more_than_once(), step + 1, bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y);
+}
+///   cargo run -- [--start black|white]   # the panel the robot starts on, for part 1 (part 2 starts on white)
+///   cargo run -- --ant 11000             # Langton's ant instead of the Intcode program
+///   cargo run -- --script 1,0,0,0,1,0    # or set paint/turn moves
+const USAGE: &str = "Usage: cargo run -- [--start black|white] [--ant <steps> | --script <paint>,<turn>,...] [run options]";
+fn usage(msg: String) -> Error {
+    eprintln!("{}", USAGE);
+    Error::Usage { msg }
+}
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 2000;
+    let mut start = Black;
+    let mut brain: Option<(Box<dyn Brain>, &str)> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" => start = match args.next().as_deref() {
+                Some("black") => Black,
+                Some("white") => White,
+                other => return Err(usage(format!("--start is black or white, not {:?}", other))),
+            },
+            "--ant" => {
+                let steps = args.next().and_then(|n| n.parse().ok()).ok_or_else(|| usage("--ant needs a number of steps".to_string()))?;
+                brain = Some((Box::new(LangtonsAnt { steps }), "Langton's ant"));
+            },
+            "--script" => {
+                let script = args.next().ok_or_else(|| usage("--script needs moves".to_string()))?;
+                brain = Some((Box::new(ScriptedBrain::parse(&script)?), "The script"));
+            },
+            _ => (), // RunConfig's
+        }
+    }
+    let config = RunConfig::from_args();
+    if let Some((mut brain, name)) = brain {
+        let hull = block_on(robot_run(&mut *brain, start))?;
+        let painting = hull.picture();
+        print!("{}", painting);
+        println!("{} painted {} panels in {} moves", name, hull.painted(), hull.bounds.len());
+        report(&hull);
+        config.save_image(&painting);
+        return Ok(());
+    }
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
         prog_orig.append(&mut extra_space);
     };
-    let list_of_paint_color_by_location = match block_on(boot_intcode_and_robot(prog_orig.clone())) {
-        Ok(list) => list,
-        Err(e) => return Err(e),
-    };
-    println!("Part 1: {} locations painted at least once", list_of_paint_color_by_location.len());
-    // Print out painting result:
-    let (min_x, min_y, max_x, max_y) = list_of_paint_color_by_location.iter().fold((0,0,0,0), |(min_x, min_y, max_x, max_y), ((x,y),_color)| {
-        (
-            if *x < min_x {*x} else { min_x },
-            if *y < min_y {*y} else { min_y },
-            if *x > max_x {*x} else { max_x },
-            if *y > max_y {*y} else { max_y },
-        )
-    });
-    for y in (min_y..=max_y).rev() {
-        for x in min_x..=max_x {
-            let ch = match list_of_paint_color_by_location.get(&(x,y)) {
-                Some(color) => match color {
-                    Black => ' ',
-                    White => '#',
-                },
-                None => ' ',
-            };
-            print!("{}", ch);
-        }
-        println!("");
+    let hull = block_on(boot_intcode_and_robot(prog_orig.clone(), start))?;
+    println!("Part 1: {} locations painted at least once", hull.painted());
+    report(&hull);
+    // Print out painting result, and save it as a picture if asked:
+    let hull = block_on(boot_intcode_and_robot(prog_orig.clone(), White))?;
+    let painting = hull.picture();
+    print!("{}", painting);
+    config.save_image(&painting);
+    match Font::standard().read(&painting) {
+        Ok(letters) => println!("Part 2: registration identifier is {}", letters),
+        Err(e) => println!("Part 2: {}", e),
     }
+    report(&hull);
     Ok(())
 }
+
+#[test]
+fn test_brains() {
+    let mut example = ScriptedBrain::parse("1,0, 0,0, 1,0, 1,0, 0,1, 1,0, 1,0").unwrap();
+    assert_eq!(example.moves[4], (Black,Right));
+    assert!(matches!(ScriptedBrain::parse("1,x"), Err(Error::BadScript { msg }) if msg.contains("'x'")));
+    assert!(matches!(ScriptedBrain::parse("1,0,1"), Err(Error::BadScript { .. })));
+    let hull = block_on(robot_run(&mut example, Black)).unwrap();
+    assert_eq!((hull.painted(), hull.painted_more_than_once()), (6, 1));
+    assert_eq!(hull.last_growth(), (5, Bounds { min_x: -1, min_y: -1, max_x: 1, max_y: 1 }));
+    assert_eq!(hull.picture(), "  #\n  #\n## \n");
+    let hull = block_on(robot_run(&mut LangtonsAnt { steps: 4 }, Black)).unwrap();
+    assert_eq!(hull.painted(), 4);
+    assert_eq!(hull.panels.values().filter(|color| **color == White).count(), 4);
+}
@@ -1,165 +1,40 @@
 /// https://adventofcode.com/2019/day/13#part2
 extern crate crossterm;
-const ESC_CLS:&'static str = "\x1B[2J";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
-use std::convert::{TryFrom, TryInto};
+mod policy;
+mod save;
+
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::collections::BTreeMap;
+use std::env;
 use futures::prelude::*;
 use futures::channel::mpsc::{channel,Sender,Receiver};
 use futures::executor::block_on;
 use futures::join;
+use intcode::Intcode;
+use intcode::cheat::{Cheats, CheatSearch, Filter};
 use TileID::*;
 use JoystickPosition::*;
 use std::time::Duration;
+use render::{Renderer, RunConfig};
+use policy::{FollowBall, JoystickPolicy, Keyboard, Predictive};
+use save::{HighScore, HighScores, SaveState};
 
 #[derive(Debug)]
 enum Error {
-    IllegalOpcode { code: isize },
     IllegalTileID { val: isize },
     ArcadeComms { msg: String },
-    ComputerComms { msg: String },
-}
-#[derive(Debug)]
-enum OpCode {
-    Add = 1,
-    Multiply = 2,
-    Read = 3,
-    Write = 4,
-    BranchNE = 5,
-    BranchEQ = 6,
-    CompareLT = 7,
-    CompareEQ = 8,
-    AdjustBase = 9,
-    Halt = 99,
+    ComputerError { internal: intcode::Error },
+    Terminal { msg: String },
+    SaveFile { msg: String },
+    Usage { msg: String },
 }
-impl TryFrom<isize> for OpCode {
-    type Error = Error;
-    fn try_from(code: isize) -> Result<Self, Self::Error> {
-        use OpCode::*;
-        let opcode = match code {
-            1 => Add,
-            2 => Multiply,
-            3 => Read,
-            4 => Write,
-            5 => BranchNE,
-            6 => BranchEQ,
-            7 => CompareLT,
-            8 => CompareEQ,
-            9 => AdjustBase,
-            99 => Halt,
-            _ => return Err(Error::IllegalOpcode { code }),
-        };
-        Ok(opcode)
+impl From<intcode::Error> for Error {
+    fn from(internal: intcode::Error) -> Self {
+        Error::ComputerError { internal }
     }
 }
-async fn intcode_run(mut v: Vec<isize>, mut input: Receiver<isize>, mut output: Sender<isize>) -> Result<Receiver<isize>, Error> {
-    let mut pc: usize = 0;
-    let mut relative_base: isize = 0;
-    loop {
-        use OpCode::*;
-        let mode = v[pc] / 100;
-        let op = (v[pc] - mode * 100).try_into()?;
-        let m1 = mode - mode / 10 * 10;  let mode = mode / 10;
-        let m2 = mode - mode / 10 * 10;  let mode = mode / 10;
-        let m3 = mode - mode / 10 * 10;  let mode = mode / 10;
-        assert_eq!(mode, 0);
-        // if !op.execute(&mut v, mode) { break; }
-        match op {
-            Add => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let p3 = v[pc + 3];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                assert_ne!(m3, 1);
-                v[ if m3 ==2 {p3+relative_base} else {p3} as usize] = v1 + v2;
-                pc += 4;
-            }
-            Multiply => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let p3 = v[pc + 3];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                assert_ne!(m3, 1);
-                v[ if m3 ==2 {p3+relative_base} else {p3} as usize] = v1 * v2;
-                pc += 4;
-            }
-            Read => {
-                let p1 = v[pc + 1];
-                assert_ne!(m1, 1);
-                v[ if m1 ==2 {p1+relative_base} else {p1} as usize] = match input.next().await {
-                    Some(v) => v,
-                    None => return Err(Error::ComputerComms{msg:"Expecting input, but stream has terminated.".to_string()}),
-                };
-                pc += 2;
-            }
-            Write => {
-                let p1 = v[pc + 1];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                if let Err(_) = output.send(v1).await {
-                    return Err(Error::ComputerComms{msg:"Problem sending output data. Has receiver been dropped?".to_string()});
-                };
-                pc += 2;
-            }
-            BranchNE => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                if v1 != 0 {
-                    pc = v2 as usize
-                } else {
-                    pc += 3
-                };
-            }
-            BranchEQ => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                if v1 == 0 {
-                    pc = v2 as usize
-                } else {
-                    pc += 3
-                };
-            }
-            CompareLT => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let p3 = v[pc + 3];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                assert_ne!(m3, 1);
-                v[ if m3 ==2 {p3+relative_base} else {p3} as usize] = if v1 < v2 {1} else {0};
-                pc += 4;
-            }
-            CompareEQ => {
-                let p1 = v[pc + 1];
-                let p2 = v[pc + 2];
-                let p3 = v[pc + 3];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                let v2 = match m2 { 0=>v[p2 as usize], 1=>p2, 2=>v[(p2+relative_base) as usize], _ => panic!("Bad Mode"),};
-                assert_ne!(m3, 1);
-                v[ if m3 ==2 {p3+relative_base} else {p3} as usize] = if v1 == v2 {1} else {0};
-                pc += 4;
-            }
-            AdjustBase => {
-                let p1 = v[pc + 1];
-                let v1 = match m1 { 0=>v[p1 as usize], 1=>p1, 2=>v[(p1+relative_base) as usize], _ => panic!("Bad Mode"),};
-                relative_base += v1;
-                pc += 2;
-            }
-            Halt => break,
-        }
-    }
-    Ok(input) // Drop the input Receiver, this allow downstream fetching of values we are not going to process ('cause we're done)
-}
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum JoystickPosition {
     Left=-1,
@@ -201,38 +76,165 @@ impl TileID {
         }
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
+/// What the arcade's screen shows, as the game draws it
+#[derive(Debug,Clone,Default)]
+struct ArcadeState {
+    tiles: BTreeMap<(isize,isize),TileID>, // by (y,x)
+    ball: Option<(isize,isize)>,
+    ball_velocity: Option<(isize,isize)>, // (dy,dx) of the ball's last move
+    paddle: Option<(isize,isize)>,
+    score: isize,
+    frames: usize, // joystick moves so far
+}
+impl ArcadeState {
+    fn update(&mut self, y: isize, x: isize, tile_id: TileID) {
+        self.tiles.insert((y,x), tile_id);
+        match tile_id {
+            Ball => {
+                if let Some(prior) = self.ball {
+                    self.ball_velocity = Some((y - prior.0, x - prior.1));
+                }
+                self.ball = Some((y,x));
+            },
+            HorizontalPaddle => self.paddle = Some((y,x)),
+            _ => (),
+        }
+    }
+    fn blocks(&self) -> usize {
+        self.tiles.values().filter(|tile_id| **tile_id == Block).count()
+    }
 }
-fn print_ch(ch: char) {
-    print!("{}",ch);
-    stdout().flush().unwrap();
+/// Why the game stopped
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+enum Ending {
+    Halted, // the program halted: game over, or every block broken
+    Quit,   // the policy stopped playing
 }
-fn set_cursor_pos(y:isize,x:isize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
+// What the screen showed as one joystick input was sent
+#[derive(Debug,Copy,Clone)]
+struct Frame {
+    ball: (isize,isize),
+    ball_dy: Option<isize>,
+    paddle_x: Option<isize>,
+    score: isize,
 }
-fn set_color(color:u8) {
-    print(
-        &format!("\x1B[{}m", 41 + color)
-    );
+// Cheat-Engine-style search for the ball, paddle and score in Intcode memory.  Each frame, every
+// candidate address whose value doesn't match the screen is dropped.  Once the ball's vertical
+// velocity is found, it gets flipped whenever the ball is about to fall past the paddle.
+struct Cheater {
+    cheats: Cheats,
+    frames: Vec<Frame>,
+    searches: Option<[CheatSearch; 4]>, // ball x, ball dy, paddle x, score
+    searched: usize, // frames folded into the searches so far
+    saves: usize,
 }
-async fn arcade_run(mut rx: Receiver<isize>, mut tx: Sender<isize>) -> Result<isize,Error> {
-    const BALL_COLOR: u8 = 50;
+impl Cheater {
+    fn new(cheats: Cheats) -> Self {
+        Cheater { cheats, frames: Vec::new(), searches: None, searched: 0, saves: 0 }
+    }
+    // Called as each joystick input is about to be sent.  True once the cheat keeps the ball in play.
+    fn next_frame(&mut self, ball: (isize,isize), paddle: (isize,isize), score: isize) -> bool {
+        // Snapshot N was taken as input N arrived, so it matches frame N
+        let frame_count = self.cheats.frames();
+        if frame_count > self.searched {
+            let snapshot = self.cheats.snapshot();
+            let seen = self.frames[frame_count - 1];
+            let searches = self.searches.get_or_insert_with(|| {
+                let everything = CheatSearch::new(&snapshot);
+                [everything.clone(), everything.clone(), everything.clone(), everything]
+            });
+            searches[0].refine(&snapshot, Filter::Equals(seen.ball.1));
+            if let Some(dy) = seen.ball_dy {
+                searches[1].refine(&snapshot, Filter::Equals(dy));
+            }
+            if let Some(x) = seen.paddle_x {
+                searches[2].refine(&snapshot, Filter::Equals(x));
+            }
+            searches[3].refine(&snapshot, Filter::Equals(seen.score));
+            self.searched = frame_count;
+        }
+        let mut ball_dy = self.frames.last().map(|prior| ball.0 - prior.ball.0);
+        let dy_addr = self.searches.as_ref().and_then(|searches| searches[1].found());
+        if let Some(addr) = dy_addr {
+            if ball_dy == Some(1) && ball.0 + 1 == paddle.0 {
+                self.cheats.poke(addr, -1);
+                ball_dy = Some(-1); // as the snapshot will see it
+                self.saves += 1;
+            }
+        }
+        // The paddle isn't drawn until after the first ball
+        let paddle_x = if paddle == (0,0) {None} else {Some(paddle.1)};
+        self.frames.push(Frame { ball, ball_dy, paddle_x, score });
+        dy_addr.is_some()
+    }
+    fn report(&self) {
+        let names = ["Ball x", "Ball dy", "Paddle x", "Score"];
+        if let Some(searches) = &self.searches {
+            for (name, search) in names.iter().zip(searches.iter()) {
+                match search.candidates() {
+                    [addr] => println!("{} is at address {}", name, addr),
+                    candidates => println!("{} is one of {} addresses", name, candidates.len()),
+                }
+            }
+        }
+        println!("The cheat saved the ball {} times in {} frames", self.saves, self.frames.len());
+    }
+}
+fn next_block_color(block_color: &mut u8) -> u8 {
+    let color = *block_color;
+    *block_color += 1;
+    if *block_color > 97 {*block_color = 89;}
+    color
+}
+// A resumed game starts with the computer waiting for the joystick; `quit_after` moves, the policy is stopped
+async fn arcade_run(mut rx: Receiver<isize>, mut tx: Sender<isize>, mut cheater: Option<Cheater>, mut screen: Box<dyn Renderer>, policy: &mut dyn JoystickPolicy, resume: Option<ArcadeState>, quit_after: Option<usize>) -> Result<(ArcadeState,Ending),Error> {
+    const BALL_COLOR: u8 = 91; // ANSI bright red
      
-    let mut arcade_screen = BTreeMap::new(); // grid of tiles
-    let mut score: isize = 0;
-    let mut ball_position: (isize,isize);
-    let mut paddle_position: (isize,isize) = (0,0);
-    let mut block_color: u8 = 48;
+    let mut pending_move = resume.is_some();
+    let mut state = resume.unwrap_or_default();
+    let mut ending = Ending::Halted;
+    let mut block_color: u8 = 89;
 
     // Do Not Print out WHOLE SCREEN on every character change: (too slow?)
-    // print!("\u{001Bc}"); // clear screen, reset cursor
-    print(ESC_CLS); // clear screen, reset cursor
-    print(ESC_CURSOR_OFF);
+    screen.clear();
+    for ((y,x), tile_id) in &state.tiles {
+        let color = match tile_id {
+            Block => Some(next_block_color(&mut block_color)),
+            Ball => Some(BALL_COLOR),
+            _ => None,
+        };
+        screen.draw_cell(*y as usize, *x as usize, tile_id.to_char(), color);
+    }
+    if pending_move {
+        screen.draw_text(25, 0, &format!("Score: {}", state.score));
+        screen.flush_frame();
+    }
     // process all messages
     loop {
+        // Control Joystick via Intcode Input, once the ball has moved
+        if pending_move && ending == Ending::Halted {
+            pending_move = false;
+            let decision = if Some(state.frames) == quit_after {None} else {policy.decide(&state)};
+            match decision {
+                Some(joystick_position) => {
+                    // Once the cheat can keep the ball in play, the joystick can rest
+                    let cheating = match &mut cheater {
+                        Some(cheater) => cheater.next_frame(state.ball.unwrap_or((0,0)), state.paddle.unwrap_or((0,0)), state.score),
+                        None => false,
+                    };
+                    let joystick_position = if cheating {Neutral} else {joystick_position};
+                    if let Err(_) = tx.send(joystick_position as isize).await {
+                        return Err(Error::ArcadeComms { msg:format!("Arcade output channel failure.  The following data is being discarded:\n   {:?}", joystick_position) });
+                    }
+                    state.frames += 1;
+                },
+                None => {
+                    // Without input the computer stops at its next Read; take the screen up to there
+                    ending = Ending::Quit;
+                    tx.close_channel();
+                },
+            }
+        }
         // Intcode Output
         let x = match rx.next().await {
             Some(x) => x,
@@ -243,10 +245,10 @@ async fn arcade_run(mut rx: Receiver<isize>, mut tx: Sender<isize>) -> Result<is
             None => break,
         };
         if (0,-1) == (y,x) {
-            score = match rx.next().await {
+            state.score = match rx.next().await {
                 Some(score) => {
-                    set_cursor_pos(24,0);
-                    println!("\nScore: {}\n\n", score);
+                    screen.draw_text(25, 0, &format!("Score: {}", score));
+                    screen.flush_frame();
                     score
                 },
                 None => break,
@@ -256,38 +258,18 @@ async fn arcade_run(mut rx: Receiver<isize>, mut tx: Sender<isize>) -> Result<is
                 Some(tile_val) => TileID::try_from(tile_val)?,
                 None => break,
             };
-            if tile_id == Block {
-                set_color(block_color);
-                block_color += 1;
-                if block_color > 56 {block_color = 48;}
-            }
+            let color = match tile_id {
+                Block => Some(next_block_color(&mut block_color)),
+                Ball => Some(BALL_COLOR),
+                _ => None,
+            };
+            screen.draw_cell(y as usize, x as usize, tile_id.to_char(), color);
+            state.update(y, x, tile_id);
             if tile_id == Ball {
-                set_color(BALL_COLOR);
-            }
-            set_cursor_pos(y,x);
-            print_ch(tile_id.to_char());
-            set_color(56); // Bright White?
-            match tile_id {
-                Ball => {
-                    ball_position = (y,x);
-                    // Control Joystick via Intcode Input
-                    let joystick_position = if paddle_position.1 == ball_position.1 {
-                        Neutral
-                    } else if paddle_position.1 < ball_position.1 {
-                        Right
-                    } else {
-                        Left
-                    };
-                    if let Err(_) = tx.send(joystick_position as isize).await {
-                        return Err(Error::ArcadeComms { msg:format!("Arcade output channel failure.  The following data is being discarded:\n   {:?}", joystick_position) });
-                    }
-                },
-                HorizontalPaddle => {
-                    paddle_position = (y,x);
-                },
-                _ => (),
+                // A frame per tick of the game
+                screen.flush_frame();
+                pending_move = true;
             }
-            if let Some(_prior_tile) = arcade_screen.insert((y,x),tile_id) {};    
         }
         // // DEBUG PRINT WHOLE SCREEN
         // print(ESC_CLS); // clear screen, reset cursor
@@ -328,40 +310,90 @@ async fn arcade_run(mut rx: Receiver<isize>, mut tx: Sender<isize>) -> Result<is
         //     std::thread::sleep(delay);
         // }
     }
-    Ok(score)
+    screen.finish();
+    if let Some(cheater) = cheater {
+        cheater.report();
+    }
+    Ok((state, ending))
 }
-async fn boot_intcode_and_arcade(prog: Vec<isize>) -> Result<isize,Error> {
+async fn boot_intcode_and_arcade(prog: Vec<isize>, resume: Option<SaveState>, cheat: bool, screen: Box<dyn Renderer>, policy: &mut dyn JoystickPolicy, quit_after: Option<usize>) -> Result<(SaveState,Ending),Error> {
     const BUFFER_SIZE: usize = 10;
     let (arcade_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, arcade_rx) = channel::<isize>(BUFFER_SIZE);
-    let mut hacked_program = prog.clone();
-    hacked_program[0] = 2;
-    let computer = intcode_run(hacked_program, computer_rx, computer_tx);
-    let arcade = arcade_run(arcade_rx, arcade_tx);
-    let (_computer_return,final_score) = join!(computer, arcade); // , computer_snooper.monitor(), arcade_snooper.monitor()
-    final_score
+    let mut computer = Intcode::new(prog, computer_rx, computer_tx);
+    let resume = resume.map(|saved| {
+        computer.set_state(saved.vm);
+        saved.arcade
+    });
+    let cheater = if cheat {
+        let cheats = Cheats::new();
+        computer.set_cheats(Some(cheats.clone()));
+        Some(Cheater::new(cheats))
+    } else {
+        None
+    };
+    let arcade = arcade_run(arcade_rx, arcade_tx, cheater, screen, policy, resume, quit_after);
+    let (computer_return,arcade_return) = join!(computer.run_to_halt(), arcade); // , computer_snooper.monitor(), arcade_snooper.monitor()
+    let (arcade, ending) = arcade_return?;
+    // Quitting leaves the computer at a Read of input that won't come
+    if ending == Ending::Halted {
+        computer_return?;
+    }
+    Ok((SaveState { vm: computer.state(), arcade }, ending))
+}
+const USAGE: &str = "Usage: cargo run -- [--policy follow|predict|keyboard] [--cheat] [--resume <file>] [--save <file>] [--quit-after <moves>] [run options]";
+fn usage(msg: String) -> Error {
+    eprintln!("{}", USAGE);
+    Error::Usage { msg }
 }
 fn main() -> Result<(),Error> {
-    const PROG_MEM_SIZE: usize = 3000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
-    // Add some empty space for code growth
-    if prog_orig.len() < PROG_MEM_SIZE {
-        let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
-        prog_orig.append(&mut extra_space);
+    // freeplay.patch puts two quarters in the slot
+    let prog_orig = intcode::program::load_patched(filename, &["freeplay.patch"])?;
+    let option = |name: &str| env::args().skip_while(|arg| arg != name).nth(1);
+    // --cheat: find the game's variables in memory while playing, then use them to keep the ball in play
+    let cheat = env::args().any(|arg| arg == "--cheat");
+    // --policy follow|predict|keyboard: who works the joystick (see policy.rs)
+    let mut policy: Box<dyn JoystickPolicy> = match option("--policy").as_deref() {
+        None | Some("follow") => Box::new(FollowBall),
+        Some("predict") => Box::new(Predictive),
+        Some("keyboard") => Box::new(Keyboard::new(Duration::from_millis(150))?),
+        Some(other) => return Err(usage(format!("--policy is follow, predict or keyboard, not {}", other))),
     };
-    let final_score = match block_on(boot_intcode_and_arcade(prog_orig.clone())) {
-        Ok(score) => score,
+    // --save, --resume and --quit-after: see save.rs
+    let resume = option("--resume").map(|filename| SaveState::load(&filename)).transpose()?;
+    let quit_after = option("--quit-after")
+        .map(|moves| moves.parse().map_err(|_| usage(format!("--quit-after needs a number of moves, not {}", moves))))
+        .transpose()?;
+    // A frame per move of the ball, as fast as the terminal takes them unless --fps or --enter
+    let screen = RunConfig::from_args().renderer(Duration::from_millis(0));
+    let (saved, ending) = match block_on(boot_intcode_and_arcade(prog_orig.clone(), resume, cheat, screen, &mut *policy, quit_after)) {
+        Ok(result) => result,
         Err(e) => return Err(e),
     };
-    println!("Part 2: Final score is {}", final_score );
+    let name = if cheat {format!("{}+cheat", policy.name())} else {policy.name().to_string()};
+    drop(policy); // the keyboard gives the terminal back
+    let state = &saved.arcade;
+    match ending {
+        Ending::Halted => {
+            println!("Part 2: Final score is {}", state.score );
+            let mut scores = HighScores::beside_binary()?;
+            let entry = HighScore { score: state.score, blocks: state.blocks(), frames: state.frames, policy: name };
+            match scores.add(entry) {
+                Some(rank) => {
+                    scores.save()?;
+                    println!("That's number {} on the high-score table:\n{}", rank, scores.table());
+                },
+                None => println!("Not a high score"),
+            }
+        },
+        Ending::Quit => {
+            println!("The {} player quit after {} moves with a score of {} and {} blocks left", name, state.frames, state.score, state.blocks());
+            if let Some(filename) = option("--save") {
+                saved.save(&filename)?;
+                println!("Saved to {}", filename);
+            }
+        },
+    }
     Ok(())
 }
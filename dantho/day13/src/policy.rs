@@ -0,0 +1,145 @@
+//! Who works the joystick.  Each time the ball moves, the policy sees the screen and picks a
+//! direction:
+//! ```text
+//! cargo run -- --policy follow     # the paddle chases the ball (the default)
+//! cargo run -- --policy predict    # the paddle waits where the ball will come down
+//! cargo run -- --policy keyboard   # you play: arrow keys or a/d, q to quit (or save, see save.rs)
+//! ```
+
+use std::time::{Duration, Instant};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use crate::{ArcadeState, Error, JoystickPosition, TileID};
+use JoystickPosition::*;
+
+pub trait JoystickPolicy: Send {
+    fn name(&self) -> &'static str;
+    /// Which way to push, or None to stop playing
+    fn decide(&mut self, state: &ArcadeState) -> Option<JoystickPosition>;
+}
+
+fn toward(paddle_x: isize, x: isize) -> JoystickPosition {
+    if paddle_x == x {
+        Neutral
+    } else if paddle_x < x {
+        Right
+    } else {
+        Left
+    }
+}
+
+/// Keep the paddle under the ball
+pub struct FollowBall;
+impl JoystickPolicy for FollowBall {
+    fn name(&self) -> &'static str {
+        "follow"
+    }
+    fn decide(&mut self, state: &ArcadeState) -> Option<JoystickPosition> {
+        match (state.paddle, state.ball) {
+            (Some(paddle), Some(ball)) => Some(toward(paddle.1, ball.1)),
+            _ => Some(Neutral),
+        }
+    }
+}
+
+/// Move the paddle to where the falling ball will reach it, bouncing off the side walls on the
+/// way.  Blocks it hits change its course, but it's predicted afresh every move.
+pub struct Predictive;
+impl Predictive {
+    /// The ball's column when it's just above the paddle, if it's falling toward it
+    pub fn landing_x(state: &ArcadeState) -> Option<isize> {
+        let (paddle, ball, (dy, dx)) = (state.paddle?, state.ball?, state.ball_velocity?);
+        if dy <= 0 || ball.0 >= paddle.0 {
+            return None;
+        }
+        let walls = state.tiles.iter().filter(|(_, tile)| **tile == TileID::Wall).map(|((_, x), _)| *x);
+        let (left, right) = walls.fold((isize::MAX, isize::MIN), |(left, right), x| (left.min(x), right.max(x)));
+        let (mut x, mut dx) = (ball.1, dx);
+        for _ in ball.0..paddle.0 - 1 {
+            if x + dx <= left || x + dx >= right {
+                dx = -dx;
+            }
+            x += dx;
+        }
+        Some(x)
+    }
+}
+impl JoystickPolicy for Predictive {
+    fn name(&self) -> &'static str {
+        "predict"
+    }
+    fn decide(&mut self, state: &ArcadeState) -> Option<JoystickPosition> {
+        let paddle = match state.paddle {
+            Some(paddle) => paddle,
+            None => return Some(Neutral),
+        };
+        match (Predictive::landing_x(state), state.ball) {
+            (Some(x), _) => Some(toward(paddle.1, x)),
+            (None, Some(ball)) => Some(toward(paddle.1, ball.1)),
+            (None, None) => Some(Neutral),
+        }
+    }
+}
+
+/// A person at the keyboard.  The game waits `tick` for each move, the paddle going whichever
+/// way was last pressed during it, and staying put if nothing was.
+pub struct Keyboard {
+    tick: Duration,
+}
+impl Keyboard {
+    pub fn new(tick: Duration) -> Result<Self, Error> {
+        terminal::enable_raw_mode().map_err(|e| Error::Terminal { msg: format!("Can't read keys: {}", e) })?;
+        Ok(Keyboard { tick })
+    }
+}
+impl Drop for Keyboard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+impl JoystickPolicy for Keyboard {
+    fn name(&self) -> &'static str {
+        "keyboard"
+    }
+    fn decide(&mut self, _state: &ArcadeState) -> Option<JoystickPosition> {
+        let deadline = Instant::now() + self.tick;
+        let mut joystick = Neutral;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(left).unwrap_or(false) {
+                return Some(joystick);
+            }
+            if let Ok(Event::Key(KeyEvent { code, modifiers })) = event::read() {
+                joystick = match code {
+                    KeyCode::Left | KeyCode::Char('a') => Left,
+                    KeyCode::Right | KeyCode::Char('d') => Right,
+                    KeyCode::Down | KeyCode::Char('s') | KeyCode::Char(' ') => Neutral,
+                    KeyCode::Esc | KeyCode::Char('q') => return None,
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return None,
+                    _ => joystick,
+                };
+            }
+        }
+    }
+}
+
+#[test]
+fn test_policies() {
+    // Walls at x 0 and 6, ball at (2,4) heading down and right, paddle on row 6
+    let mut state = ArcadeState::default();
+    for y in 0..7 {
+        state.update(y, 0, TileID::Wall);
+        state.update(y, 6, TileID::Wall);
+    }
+    state.update(6, 1, TileID::HorizontalPaddle);
+    state.update(1, 3, TileID::Ball);
+    state.update(2, 4, TileID::Ball);
+    assert_eq!(state.ball_velocity, Some((1, 1)));
+    // (3,5), then off the wall to (4,4), (5,3)
+    assert_eq!(Predictive::landing_x(&state), Some(3));
+    assert_eq!(Predictive.decide(&state), Some(Right));
+    assert_eq!(FollowBall.decide(&state), Some(Right));
+    state.update(6, 3, TileID::HorizontalPaddle);
+    assert_eq!(Predictive.decide(&state), Some(Neutral));
+    assert_eq!(FollowBall.decide(&state), Some(Right));
+}
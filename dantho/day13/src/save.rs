@@ -0,0 +1,182 @@
+//! Saved games and the high-score table, both plain text.
+//! ```text
+//! cargo run -- --policy keyboard --save breakout.sav      # q saves the game rather than losing it
+//! cargo run -- --resume breakout.sav --policy predict     # and carry on, any way you like
+//! cargo run -- --quit-after 500 --save breakout.sav       # stop the computer players part way
+//! ```
+//! A saved game is the Intcode computer (stopped at the Read of the next joystick move) and the
+//! screen as it stood.  Games played to the end go in `day13_scores.txt`, next to the binary.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::path::PathBuf;
+use crate::{ArcadeState, Error, TileID};
+
+pub struct SaveState {
+    pub vm: intcode::State,
+    pub arcade: ArcadeState,
+}
+impl SaveState {
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        let (vm, arcade) = (&self.vm, &self.arcade);
+        let mut text = String::from("# day13 saved game\n");
+        text += &format!("pc {}\nrelative_base {}\nsteps {}\n", vm.pc, vm.relative_base, vm.steps);
+        let memory: Vec<_> = vm.memory.iter().map(|val| val.to_string()).collect();
+        text += &format!("memory {}\n", memory.join(","));
+        text += &format!("score {}\nframes {}\n", arcade.score, arcade.frames);
+        let pairs = [("ball", arcade.ball), ("ball_velocity", arcade.ball_velocity), ("paddle", arcade.paddle)];
+        for (name, pair) in pairs.iter() {
+            if let Some((a, b)) = pair {
+                text += &format!("{} {} {}\n", name, a, b);
+            }
+        }
+        text += "screen\n";
+        let (max_y, max_x) = arcade.tiles.keys().fold((-1, -1), |(max_y, max_x), (y, x)| (max_y.max(*y), max_x.max(*x)));
+        for y in 0..=max_y {
+            text += &(0..=max_x).map(|x| {
+                arcade.tiles.get(&(y, x)).map(|tile_id| (b'0' + *tile_id as u8) as char).unwrap_or('.')
+            }).collect::<String>();
+            text.push('\n');
+        }
+        fs::write(filename, text).map_err(|e| bad(format!("Failure writing {}: {}", filename, e)))
+    }
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let text = fs::read_to_string(filename).map_err(|e| bad(format!("Failure opening {}: {}", filename, e)))?;
+        let mut vm = intcode::State { memory: Vec::new(), pc: 0, relative_base: 0, steps: 0 };
+        let mut arcade = ArcadeState::default();
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.starts_with('#'));
+        while let Some((line_no, line)) = lines.next() {
+            let malformed = || bad(format!("{}:{}: can't make sense of '{}'", filename, line_no + 1, line));
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or("");
+            let values = words.map(|word| word.parse::<isize>()).collect::<Result<Vec<_>, _>>();
+            let numbers = if key == "memory" || key == "screen" {Vec::new()} else {values.map_err(|_| malformed())?};
+            match (key, numbers.as_slice()) {
+                ("pc", [pc]) => vm.pc = *pc as usize,
+                ("relative_base", [base]) => vm.relative_base = *base,
+                ("steps", [steps]) => vm.steps = *steps as usize,
+                ("score", [score]) => arcade.score = *score,
+                ("frames", [frames]) => arcade.frames = *frames as usize,
+                ("ball", [y, x]) => arcade.ball = Some((*y, *x)),
+                ("ball_velocity", [dy, dx]) => arcade.ball_velocity = Some((*dy, *dx)),
+                ("paddle", [y, x]) => arcade.paddle = Some((*y, *x)),
+                ("memory", _) => {
+                    let memory = line["memory".len()..].trim().split(',').map(|val| val.parse::<isize>());
+                    vm.memory = memory.collect::<Result<_, _>>().map_err(|_| malformed())?;
+                },
+                ("screen", _) => {
+                    for (y, (_, row)) in lines.by_ref().enumerate() {
+                        for (x, ch) in row.chars().enumerate() {
+                            if let Some(digit) = ch.to_digit(10) {
+                                arcade.tiles.insert((y as isize, x as isize), TileID::try_from(digit as isize)?);
+                            }
+                        }
+                    }
+                },
+                _ => return Err(malformed()),
+            }
+        }
+        if vm.memory.is_empty() {
+            return Err(bad(format!("{} has no computer memory", filename)));
+        }
+        Ok(SaveState { vm, arcade })
+    }
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct HighScore {
+    pub score: isize,
+    pub blocks: usize, // left unbroken
+    pub frames: usize,
+    pub policy: String,
+}
+impl HighScore {
+    fn parse(line: &str) -> Option<Self> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [score, blocks, frames, policy] => Some(HighScore {
+                score: score.parse().ok()?,
+                blocks: blocks.parse().ok()?,
+                frames: frames.parse().ok()?,
+                policy: policy.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+pub struct HighScores {
+    filename: PathBuf,
+    entries: Vec<HighScore>, // best first
+}
+impl HighScores {
+    const KEPT: usize = 10;
+    /// `day13_scores.txt` beside the running binary
+    pub fn beside_binary() -> Result<Self, Error> {
+        let exe = std::env::current_exe().map_err(|e| bad(format!("Can't find the binary: {}", e)))?;
+        HighScores::load(exe.with_file_name("day13_scores.txt"))
+    }
+    /// An empty table if there's no file yet
+    pub fn load(filename: PathBuf) -> Result<Self, Error> {
+        let text = fs::read_to_string(&filename).unwrap_or_default();
+        let entries = text.lines().filter(|line| !line.starts_with('#') && !line.trim().is_empty()).map(|line| {
+            HighScore::parse(line).ok_or_else(|| bad(format!("{}: can't make sense of '{}'", filename.display(), line)))
+        }).collect::<Result<_, _>>()?;
+        Ok(HighScores { filename, entries })
+    }
+    /// Where `entry` places (1 is best), or None if it didn't make the table
+    pub fn add(&mut self, entry: HighScore) -> Option<usize> {
+        // Ties go to whoever got there first
+        let rank = self.entries.iter().take_while(|other| other.score >= entry.score).count();
+        if rank >= HighScores::KEPT {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(HighScores::KEPT);
+        Some(rank + 1)
+    }
+    pub fn save(&self) -> Result<(), Error> {
+        fs::write(&self.filename, self.table()).map_err(|e| bad(format!("Failure writing {}: {}", self.filename.display(), e)))
+    }
+    pub fn table(&self) -> String {
+        let mut text = format!("# {:>8} {:>6} {:>8} policy\n", "score", "blocks", "frames");
+        for entry in &self.entries {
+            text += &format!("{:>10} {:>6} {:>8} {}\n", entry.score, entry.blocks, entry.frames, entry.policy);
+        }
+        text
+    }
+}
+fn bad(msg: String) -> Error {
+    Error::SaveFile { msg }
+}
+
+#[test]
+fn test_save_and_scores() {
+    let dir = std::env::temp_dir();
+    let mut arcade = ArcadeState::default();
+    arcade.update(0, 0, TileID::Wall);
+    arcade.update(1, 2, TileID::Block);
+    arcade.update(3, 1, TileID::Ball);
+    arcade.update(4, 1, TileID::HorizontalPaddle);
+    arcade.score = 42;
+    arcade.frames = 7;
+    let saved = SaveState { vm: intcode::State { memory: vec![3, 0, 99, -4], pc: 0, relative_base: 9, steps: 12 }, arcade };
+    let filename = dir.join("day13_test.sav").to_string_lossy().into_owned();
+    saved.save(&filename).unwrap();
+    let loaded = SaveState::load(&filename).unwrap();
+    assert_eq!(loaded.vm, saved.vm);
+    assert_eq!((loaded.arcade.score, loaded.arcade.frames, loaded.arcade.blocks()), (42, 7, 1));
+    assert_eq!((loaded.arcade.ball, loaded.arcade.paddle, loaded.arcade.ball_velocity), (Some((3, 1)), Some((4, 1)), None));
+    assert_eq!(loaded.arcade.tiles.get(&(0, 0)), Some(&TileID::Wall));
+    assert_eq!(loaded.arcade.tiles.len(), 4);
+
+    let filename = dir.join("day13_test_scores.txt");
+    let _ = fs::remove_file(&filename);
+    let mut scores = HighScores::load(filename.clone()).unwrap();
+    let entry = |score| HighScore { score, blocks: 0, frames: 100, policy: "follow".to_string() };
+    assert_eq!(scores.add(entry(10)), Some(1));
+    assert_eq!(scores.add(entry(30)), Some(1));
+    assert_eq!(scores.add(entry(10)), Some(3));
+    scores.save().unwrap();
+    let scores = HighScores::load(filename).unwrap();
+    assert_eq!(scores.entries.iter().map(|entry| entry.score).collect::<Vec<_>>(), [30, 10, 10]);
+}
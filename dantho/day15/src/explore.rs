@@ -0,0 +1,130 @@
+//! How the droid decides where to go next, one move at a time, from what it knows of the map:
+//! ```text
+//! cargo run -- --strategy dfs        # feel along every passage, backing out of each (the default)
+//! cargo run -- --strategy frontier   # go to the nearest unknown cell by the shortest known route
+//! cargo run -- --compare             # explore with each, and count their moves
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use crate::{DroidMovement, MapData};
+use DroidMovement::*;
+
+const DIRECTIONS: [DroidMovement; 4] = [North, South, West, East];
+
+pub trait Explorer {
+    fn name(&self) -> &'static str;
+    /// The droid's next move, or None once there's nothing left to find
+    fn next_move(&mut self, map: &BTreeMap<(isize,isize), MapData>, droid: (isize,isize)) -> Option<DroidMovement>;
+}
+
+/// Depth first: step into the first unknown neighbour, and once there are none, step back the
+/// way the droid came
+#[derive(Default)]
+pub struct DepthFirst {
+    path: Vec<DroidMovement>, // the moves out from the start to here
+    trying: Option<(DroidMovement, (isize,isize))>, // the last move, unless it was a step back
+}
+impl Explorer for DepthFirst {
+    fn name(&self) -> &'static str {
+        "dfs"
+    }
+    fn next_move(&mut self, map: &BTreeMap<(isize,isize), MapData>, droid: (isize,isize)) -> Option<DroidMovement> {
+        if let Some((dir, target)) = self.trying.take() {
+            if droid == target {
+                self.path.push(dir);
+            }
+        }
+        match DIRECTIONS.iter().find(|dir| !map.contains_key(&dir.move_from(droid))) {
+            Some(dir) => {
+                self.trying = Some((*dir, dir.move_from(droid)));
+                Some(*dir)
+            },
+            None => self.path.pop().map(|dir| dir.reverse()),
+        }
+    }
+}
+
+/// Breadth first through the known open cells to the nearest unknown one, taking the first step
+/// of that route.  Planned afresh each move, as each move may turn up a wall.
+pub struct NearestFrontier;
+impl Explorer for NearestFrontier {
+    fn name(&self) -> &'static str {
+        "frontier"
+    }
+    fn next_move(&mut self, map: &BTreeMap<(isize,isize), MapData>, droid: (isize,isize)) -> Option<DroidMovement> {
+        let mut seen = BTreeSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(droid);
+        queue.push_back((droid, None));
+        while let Some((pos, first_move)) = queue.pop_front() {
+            for dir in DIRECTIONS.iter() {
+                let next = dir.move_from(pos);
+                let first_move = first_move.or(Some(*dir));
+                match map.get(&next) {
+                    None => return first_move,
+                    Some(MapData::Wall) => (),
+                    Some(_) => if seen.insert(next) {
+                        queue.push_back((next, first_move));
+                    },
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+// Explore `maze` ('.' open, the droid starting at 'D', anything else wall) without the Intcode
+// program: the map found and the moves it took
+fn explore_maze(maze: &[&str], explorer: &mut dyn Explorer) -> (BTreeMap<(isize,isize), MapData>, usize) {
+    let mut droid = (0, 0);
+    let mut open = BTreeSet::new();
+    for (y, row) in maze.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            if ch == 'D' {
+                droid = (y as isize, x as isize);
+            }
+            if ch == '.' || ch == 'D' {
+                open.insert((y as isize, x as isize));
+            }
+        }
+    }
+    let mut map = BTreeMap::new();
+    map.insert(droid, MapData::Droid);
+    let mut moves = 0;
+    while let Some(dir) = explorer.next_move(&map, droid) {
+        moves += 1;
+        let next = dir.move_from(droid);
+        if !open.contains(&next) {
+            map.insert(next, MapData::Wall);
+        } else {
+            map.insert(droid, MapData::Empty);
+            map.insert(next, MapData::Droid);
+            droid = next;
+        }
+    }
+    (map, moves)
+}
+#[test]
+fn test_explorers() {
+    // A loop round to the south west, and dead ends east and south east
+    let maze = [
+        "###########",
+        "#....D....#",
+        "#.####.####",
+        "#.#  #.#   ",
+        "#.####...# ",
+        "#......### ",
+        "########   ",
+    ];
+    let (dfs_map, dfs_moves) = explore_maze(&maze, &mut DepthFirst::default());
+    let (frontier_map, frontier_moves) = explore_maze(&maze, &mut NearestFrontier);
+    // The same map, though the droid finishes in different places
+    let walls = |map: &BTreeMap<(isize,isize), MapData>| -> Vec<_> {
+        map.iter().map(|(pos, data)| (*pos, *data == MapData::Wall)).collect()
+    };
+    assert_eq!(walls(&dfs_map), walls(&frontier_map));
+    assert_eq!(dfs_map.values().filter(|data| **data != MapData::Wall).count(), 23);
+    assert!(!dfs_map.contains_key(&(3, 3))); // walled in
+    assert_eq!((dfs_moves, frontier_moves), (83, 79));
+}
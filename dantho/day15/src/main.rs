@@ -1,11 +1,8 @@
 /// https://adventofcode.com/2019/day/15
-const ESC_CLS: &'static str = "\x1B[2J";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
+mod explore;
+
+use std::env;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::collections::BTreeMap;
@@ -13,54 +10,84 @@ use futures::prelude::*;
 use futures::channel::mpsc::{channel,Sender,Receiver};
 use futures::executor::block_on;
 use futures::join;
-use futures::future::BoxFuture; // https://rust-lang.github.io/async-book/07_workarounds/05_recursion.html
 use DroidStatus::*;
 use DroidMovement::*;
 use MapData::*;
 use Error::*;
 use std::time::Duration;
+use render::{Renderer, RunConfig};
+use explore::{DepthFirst, Explorer, NearestFrontier};
 
+const USAGE: &str = "Usage: cargo run -- [--strategy dfs|frontier | --compare] [run options]";
+fn usage(msg: String) -> Error {
+    eprintln!("{}", USAGE);
+    Error::Usage { msg }
+}
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 3000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
         prog_orig.append(&mut extra_space);
     };
-    let (fewest_moves, most_minutes) = match block_on(boot_intcode_and_droid(prog_orig.clone())) {
-        Ok(result) => result,
-        Err(e) => return Err(e),
+    let option = |name: &str| env::args().skip_while(|arg| arg != name).nth(1);
+    // --strategy dfs|frontier: how the droid explores (see explore.rs); --compare: explore with each
+    let explorer = |name: &str| -> Result<Box<dyn Explorer>, Error> {
+        match name {
+            "dfs" => Ok(Box::new(DepthFirst::default())),
+            "frontier" => Ok(Box::new(NearestFrontier)),
+            other => Err(usage(format!("--strategy is dfs or frontier, not {}", other))),
+        }
+    };
+    let strategies = if env::args().any(|arg| arg == "--compare") {
+        vec!["dfs".to_string(), "frontier".to_string()]
+    } else {
+        vec![option("--strategy").unwrap_or_else(|| "dfs".to_string())]
     };
+    let config = RunConfig::from_args();
+    let compare = strategies.len() > 1;
+    let mut answers = None;
+    let mut report = Vec::new();
+    for strategy in strategies {
+        let mut explorer = explorer(&strategy)?;
+        // A frame per move of the droid; when comparing, each strategy records to its own files
+        let screen = if compare { config.for_run(&strategy) } else { config.clone() }.renderer(Duration::from_millis(0));
+        let (fewest_moves, most_minutes, droid_moves) = match block_on(boot_intcode_and_droid(prog_orig.clone(), screen, &mut *explorer)) {
+            Ok(result) => result,
+            Err(e) => return Err(e),
+        };
+        if answers.is_some() && answers != Some((fewest_moves, most_minutes)) {
+            return Err(Error::MapAssertFail {msg: format!("Exploring with {} found a different map", explorer.name())});
+        }
+        answers = Some((fewest_moves, most_minutes));
+        report.push(format!("Explored with {} in {} droid moves", explorer.name(), droid_moves));
+    }
+    let (fewest_moves, most_minutes) = answers.expect("At least one strategy");
     println!("");
+    for line in report {
+        println!("{}", line);
+    }
     println!("Part 1: Fewest moves to find the oxygen system is {}", fewest_moves );
     println!("Part 2: Minutes to fill every corner with oxygen is {}", most_minutes );
     Ok(())
 }
-async fn boot_intcode_and_droid(prog: Vec<isize>) -> Result<(usize,usize),Error> {
+async fn boot_intcode_and_droid(prog: Vec<isize>, screen: Box<dyn Renderer>, explorer: &mut dyn Explorer) -> Result<(usize,usize,usize),Error> {
     const BUFFER_SIZE: usize = 10;
     let (droid_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, droid_rx) = channel::<isize>(BUFFER_SIZE);
     let hacked_program = prog.clone();
     // No hacks
     let computer = intcode_run(hacked_program, computer_rx, computer_tx);
-    let droid = droid_run(droid_rx, droid_tx);
+    let droid = droid_run(droid_rx, droid_tx, screen, explorer);
     let (_computer_return,droid_response) = join!(computer, droid); // , computer_snooper.monitor(), droid_snooper.monitor()
     droid_response
 }
-async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usize),Error> {
-    let mut droid = Droid::new(rx, tx);
+async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>, explorer: &mut dyn Explorer) -> Result<(usize,usize,usize),Error> {
+    let mut droid = Droid::new(rx, tx, screen);
     droid.explored_world.redraw_screen()?;
-    droid.explore().await?;
+    droid.explore(explorer).await?;
 
     // Now that the map is fully known (by the droid)
     // Remap, replacing the known empty locations with distances from droid starting with 0 under droid.
@@ -74,8 +101,7 @@ async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usiz
 
     map_distance(&mut distance_map, (0, 0), 0)?;
 
-    let lower_right_corner = droid.explored_world.lower_right_corner_on_screen();
-    set_cursor_pos(lower_right_corner.0+1,0);
+    droid.explored_world.screen.finish();
     let distance_to_oxygen_sensor: usize = match distance_map.get(&droid.oxygen_position_if_known.unwrap()) {
         Some(d) => *d,
         None => return Err(Error::MapAssertFail {msg: format!("Can't find oxygen sensor at {:?} !", droid.oxygen_position_if_known.unwrap())}),
@@ -90,7 +116,7 @@ async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usiz
     let minutes_to_fill_with_oxygen = distance_map.iter().fold(0,|most_minutes, ((_,_), minutes)| {
         if *minutes > most_minutes {*minutes} else {most_minutes}
     });
-    Ok((distance_to_oxygen_sensor, minutes_to_fill_with_oxygen))
+    Ok((distance_to_oxygen_sensor, minutes_to_fill_with_oxygen, droid.moves))
 }
 fn map_distance(map: &mut BTreeMap<(isize,isize), usize>, loc: (isize,isize), distance: usize) -> Result<(),Error> {
     let this_loc = match map.get_mut(&loc) {
@@ -116,6 +142,13 @@ enum Error {
     ComputerComms {msg: String},
     MapAssertFail {msg: String},
     MapOriginWrong {msg: String},
+    ProgramLoad {internal: intcode::Error},
+    Usage {msg: String},
+}
+impl From<intcode::Error> for Error {
+    fn from(internal: intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
@@ -126,13 +159,12 @@ enum MapData {
 }
 // See https://jrgraphix.net/r/Unicode/2700-27BF for Dingbats in unicode
 impl MapData {
-    fn to_str(&self) -> &'static str {
-        print("\x1B[56m"); // SIDE EFFECT - print white control chars (default color)
+    fn to_char(&self) -> char {
         match *self {
-            Wall => "■",
-            Empty => ".",
-            OxygenSystem => "☻",
-            Droid => "D",
+            Wall => '■',
+            Empty => '.',
+            OxygenSystem => '☻',
+            Droid => 'D',
         }
     }
 }
@@ -197,15 +229,13 @@ impl TryFrom<isize> for DroidStatus {
 struct WorldMap {
     origin: (isize,isize),
     data: BTreeMap<(isize,isize), MapData>,
+    screen: Box<dyn Renderer>,
 }
 impl WorldMap {
-    fn new() -> Self {
+    fn new(screen: Box<dyn Renderer>) -> Self {
         let origin = (-5,-5);
         let data = BTreeMap::new();
-        WorldMap {origin, data}
-    }
-    fn is_known(&self, pos: &(isize,isize)) -> bool {
-        self.data.contains_key(pos)
+        WorldMap {origin, data, screen}
     }
     fn modify_data(&mut self, position: (isize,isize), data: MapData) -> Result<(),Error> {
         self.update_origin(position)?;
@@ -223,27 +253,27 @@ impl WorldMap {
             }
         }
         self.draw_position(position)?;
+        self.screen.flush_frame();
         Ok(())
     }
-    fn draw_position(&self, pos: (isize,isize)) -> Result<(),Error> {
+    fn draw_position(&mut self, pos: (isize,isize)) -> Result<(),Error> {
         if pos.0 < self.origin.0 || pos.1 < self.origin.1 {
             return Err(MapOriginWrong {
                 msg: format!("Map pos {:?} is lower than origin at {:?}", pos, self.origin)})}
-        set_cursor_pos(pos.0 - self.origin.0, pos.1 - self.origin.1);
         let map_item = match self.data.get(&pos) {
-            None => " ",
-            Some(data) => data.to_str(),
+            None => ' ',
+            Some(data) => data.to_char(),
         };
-        print(map_item);
+        self.screen.draw_cell((pos.0 - self.origin.0) as usize, (pos.1 - self.origin.1) as usize, map_item, None);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        for (pos, _) in &self.data {
-            self.draw_position(*pos)?;
+    fn redraw_screen(&mut self) -> Result<(),Error> {
+        self.screen.clear();
+        let positions: Vec<_> = self.data.keys().cloned().collect();
+        for pos in positions {
+            self.draw_position(pos)?;
         }
+        self.screen.flush_frame();
         Ok(())
     }
     fn update_origin(&mut self, position:(isize,isize)) -> Result<(),Error> {
@@ -258,83 +288,36 @@ impl WorldMap {
         }
         if redraw_required {
             self.redraw_screen()?;
-            set_cursor_pos(20, 20);
         }
         Ok(())
     }
-    fn lower_right_corner(&self) -> (isize,isize) {
-        self.data.iter().fold((std::isize::MIN,std::isize::MIN),|(max_y,max_x), ((y,x),_)| {
-            (
-                if *y > max_y {*y} else {max_y},
-                if *x > max_x {*x} else {max_x}
-            )
-        })
-    }
-    fn lower_right_corner_on_screen(&self) -> (isize,isize) {
-        let signed_location = self.lower_right_corner();
-        let on_screen = (signed_location.0-self.origin.0, signed_location.1-self.origin.1);
-        on_screen
-    }
-}
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
 }
-// fn print_ch(ch: char) {
-//     print!("{}",ch);
-//     stdout().flush().unwrap();
-// }
-fn set_cursor_pos(y:isize,x:isize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-// fn set_color(color:u8) {
-//     print(
-//         &format!("\x1B[{}m", 41 + color)
-//     );
-// }
 struct Droid {
     explored_world: WorldMap,
     droid_position: (isize,isize),
     oxygen_position_if_known: Option<(isize,isize)>,
+    moves: usize, // commands sent, whether or not the droid hit a wall
     rx: Receiver<isize>,
     tx: Sender<isize>,
 }
 impl Droid {
-    fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
-        let mut explored_world = WorldMap::new();
+    fn new(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Self {
+        let mut explored_world = WorldMap::new(screen);
         let droid_position: (isize,isize) = (0,0);
         let oxygen_position_if_known: Option<(isize,isize)> = None;  // Unknown as yet
         explored_world.data.insert(droid_position, MapData::Droid);
-        Droid { explored_world, droid_position, oxygen_position_if_known, rx, tx }
+        Droid { explored_world, droid_position, oxygen_position_if_known, moves: 0, rx, tx }
     }
-    // explore() is a recursive algorithm (4-way) to visit all UNVISITED squares to determine the contents.
-    // A previously visited square of any kind (preemptively) ENDS the (leg of the 4-way) recursion.
-    // See https://rust-lang.github.io/async-book/07_workarounds/05_recursion.html 
-    //    for explanation of fn syntax and async block usage
-    fn explore<'a>(&'a mut self) -> BoxFuture<'a, Result<(),Error>> {
-        async move {
-            // Explore cardinal directions, returning to center each time
-            for dir in &[North, South, West, East] {
-                let move_dir = *dir;
-                if !self.explored_world.is_known(&move_dir.move_from(self.droid_position)) {
-                    if self.move_droid(move_dir).await? {
-                        // Then explore there
-                        self.explore().await?;
-                        // and move back to continue more local exploration
-                        self.move_droid(move_dir.reverse()).await?;
-                    }
-                } 
-            }
-            Ok(())
-        }.boxed()
+    // explore() moves the droid wherever `explorer` says until it has nothing left to find
+    async fn explore(&mut self, explorer: &mut dyn Explorer) -> Result<(),Error> {
+        while let Some(move_dir) = explorer.next_move(&self.explored_world.data, self.droid_position) {
+            self.move_droid(move_dir).await?;
+        }
+        Ok(())
     }
     async fn move_droid(&mut self, move_dir: DroidMovement) -> Result<bool,Error> {
         let move_succeeded: bool;
-        // Slow things down for debug or visualization
-        // ESPECIALLY at start
-        let delay = Duration::from_millis(0);
-        std::thread::sleep(delay);
+        self.moves += 1;
         // Send a movement command to Droid's Intcode Computer
         if let Err(_) = self.tx.send(move_dir as isize).await {
             return Err(Error::DroidComms { msg:format!("Droid output channel failure.  The following data is being discarded:\n   {:?}", move_dir) });
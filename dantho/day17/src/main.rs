@@ -1,14 +1,8 @@
 /// https://adventofcode.com/2019/day/17
-const ESC_CLS: &'static str = "\x1B[2J";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 
 mod intcode;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::collections::{BTreeMap, HashSet};
 use futures::prelude::*;
@@ -19,27 +13,23 @@ use RobotMovement::*;
 use MapData::*;
 use Error::*;
 use std::time::Duration;
+use render::{Renderer, RunConfig};
 
 type Location = (isize,isize);
 
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 4000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = ::intcode::program::load(filename)?;
+    // wake.patch wakes the vacuum robot up for Part 2
+    let mut prog_awake = ::intcode::program::load_patched(filename, &["wake.patch"])?;
     // Add some empty space for code growth
-    if prog_orig.len() < PROG_MEM_SIZE {
-        let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
-        prog_orig.append(&mut extra_space);
-    };
-    let (part1,part2) = match block_on(boot_intcode_and_robot(prog_orig.clone())) {
+    for prog in [&mut prog_orig, &mut prog_awake] {
+        if prog.len() < PROG_MEM_SIZE {
+            prog.resize(PROG_MEM_SIZE, 0);
+        }
+    }
+    let (part1,part2) = match block_on(boot_intcode_and_robot(prog_orig, prog_awake, RunConfig::from_args())) {
         Ok(result) => result,
         Err(e) => return Err(e),
     };
@@ -49,31 +39,27 @@ fn main() -> Result<(),Error> {
     // println!("Part 2: xxx is {}", xxx);
     Ok(())
 }
-async fn boot_intcode_and_robot(prog: Vec<isize>) -> Result<(isize,isize),Error> {
+async fn boot_intcode_and_robot(prog: Vec<isize>, prog_awake: Vec<isize>, config: RunConfig) -> Result<(isize,isize),Error> {
     const BUFFER_SIZE: usize = 10;
     let (robot_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, robot_rx) = channel::<isize>(BUFFER_SIZE);
-    let unhacked_program = prog.clone();
-    // No hacks for Part 1
-    let computer = intcode::intcode_run(unhacked_program, computer_rx, computer_tx);
-    let robot = robot_run_part1(robot_rx, robot_tx);
+    let computer = intcode::intcode_run(prog, computer_rx, computer_tx);
+    let robot = robot_run_part1(robot_rx, robot_tx, config.for_run("part1").renderer(Duration::from_millis(0)));
     let (_computer_return,robot_response_part1) = join!(computer, robot); // , computer_snooper.monitor(), robot_snooper.monitor()
     // Part 2 **************
     let (robot_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, robot_rx) = channel::<isize>(BUFFER_SIZE);
-    // Hacks required for Part 2
-    let mut hacked_program = prog.clone();
-    hacked_program[0] = 2;
-    let computer = intcode::intcode_run(hacked_program, computer_rx, computer_tx);
-    let robot = robot_run_part2(robot_rx, robot_tx);
+    let computer = intcode::intcode_run(prog_awake, computer_rx, computer_tx);
+    let robot = robot_run_part2(robot_rx, robot_tx, config.for_run("part2").renderer(Duration::from_millis(0)));
     let (_computer_return,robot_response_part2) = join!(computer, robot); // , computer_snooper.monitor(), robot_snooper.monitor()
 
     Ok((robot_response_part1.unwrap(), robot_response_part2.unwrap()))
 }
-async fn robot_run_part1(rx: Receiver<isize>, tx: Sender<isize>) -> Result<isize,Error> {
-    let mut robot = Robot::new(rx, tx);
+async fn robot_run_part1(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Result<isize,Error> {
+    let mut robot = Robot::new(rx, tx, screen);
     robot.download_camera_view().await?;
     robot.camera_view.redraw_screen()?;
+    robot.camera_view.screen.finish();
     let intersections = robot.find_intersections()?;
     let sum_of_alignment_params = intersections.iter()
         .fold(0,|sum, (y,x)| {
@@ -82,8 +68,8 @@ async fn robot_run_part1(rx: Receiver<isize>, tx: Sender<isize>) -> Result<isize
     // println!("\nIntersections: {:?}", intersections);
     Ok(sum_of_alignment_params)
 }
-async fn robot_run_part2(rx: Receiver<isize>, tx: Sender<isize>) -> Result<isize,Error> {
-    let mut robot = Robot::new(rx, tx);
+async fn robot_run_part2(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Result<isize,Error> {
+    let mut robot = Robot::new(rx, tx, screen);
     robot.download_camera_view().await?;
     // robot.camera_view.redraw_screen()?;
     let path_to_end = robot.find_path_to_end()?;
@@ -94,6 +80,7 @@ async fn robot_run_part2(rx: Receiver<isize>, tx: Sender<isize>) -> Result<isize
     let c = "L,8,R,12,L,12";
 
     let dust = robot.execute_path(main,a,b,c).await?;
+    robot.camera_view.screen.finish();
 
     Ok(dust)
 }
@@ -107,6 +94,12 @@ enum Error {
     MapOriginWrong {msg: String},
     ImpossibleTurn {msg: String},
     CameraNotFound,
+    ProgramLoad {internal: ::intcode::Error},
+}
+impl From<::intcode::Error> for Error {
+    fn from(internal: ::intcode::Error) -> Self {
+        ProgramLoad {internal}
+    }
 }
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
@@ -123,7 +116,6 @@ enum MapData {
 // See https://jrgraphix.net/r/Unicode/2700-27BF for Dingbats in unicode
 impl MapData {
     fn to_str(&self) -> &'static str {
-        print("\x1B[56m"); // SIDE EFFECT - print white control chars (default color)
         match *self {
             Scaffold => "■",
             Empty => "•",
@@ -207,61 +199,43 @@ impl RobotMovement {
 struct WorldMap {
     origin: Location,
     data: BTreeMap<Location, MapData>,
+    screen: Box<dyn Renderer>,
 }
 impl WorldMap {
-    fn new() -> Self {
+    fn new(screen: Box<dyn Renderer>) -> Self {
         let origin = (-2,-2);
         let data = BTreeMap::new();
-        WorldMap {origin, data}
+        WorldMap {origin, data, screen}
     }
-    fn draw_position(&self, pos: Location) -> Result<(),Error> {
+    fn draw_position(&mut self, pos: Location) -> Result<(),Error> {
         if pos.0 < self.origin.0 || pos.1 < self.origin.1 {
             return Err(MapOriginWrong {
                 msg: format!("Map pos {:?} is lower than origin at {:?}", pos, self.origin)})}
-        set_cursor_pos(pos.0 - self.origin.0, pos.1 - self.origin.1);
         let map_item = match self.data.get(&pos) {
             None => " ",
             Some(data) => data.to_str(),
         };
-        print(map_item);
+        self.screen.draw_text((pos.0 - self.origin.0) as usize, (pos.1 - self.origin.1) as usize, map_item);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        for (pos, _) in &self.data {
-            self.draw_position(*pos)?;
+    fn redraw_screen(&mut self) -> Result<(),Error> {
+        self.screen.clear();
+        let positions: Vec<_> = self.data.keys().cloned().collect();
+        for pos in positions {
+            self.draw_position(pos)?;
         }
-        println!("");
+        self.screen.flush_frame();
         Ok(())
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-// fn print_ch(ch: char) {
-//     print!("{}",ch);
-//     stdout().flush().unwrap();
-// }
-fn set_cursor_pos(y:isize,x:isize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-// fn set_color(color:u8) {
-//     print(
-//         &format!("\x1B[{}m", 41 + color)
-//     );
-// }
 struct Robot {
     camera_view: WorldMap,
     rx: Receiver<isize>,
     tx: Sender<isize>,
 }
 impl Robot {
-    fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
-        let camera_view = WorldMap::new();
+    fn new(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Self {
+        let camera_view = WorldMap::new(screen);
         Robot { camera_view, rx, tx: tx }
     }
     fn find_robot(&self) -> Result<Location,Error> {
@@ -440,10 +414,6 @@ impl Robot {
         Ok(dust_collected)
     }
     async fn download_camera_view(&mut self) -> Result<(),Error> {
-        // Slow things down for debug or visualization
-        // ESPECIALLY at start
-        let delay = Duration::from_millis(0);
-        std::thread::sleep(delay);
         let mut next_coord = (0,0);
         loop {
             // Fetch the next image datapoint
@@ -1,13 +1,10 @@
 /// https://adventofcode.com/2019/day/15
-const ESC_CLS: &'static str = "\x1B[2J";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 const DBG: bool = false;
 const INFINITY: usize = std::usize::MAX/1_000_000_000_000_000*1_000_000_000_000_000-1;  // Very nearly max with lots of 999's at end to be visible as a "special" number
 
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
+use std::io::BufReader;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::collections::{BTreeMap,HashSet};
@@ -15,6 +12,8 @@ use std::sync::mpsc::{Sender,Receiver};
 use std::sync::mpsc;
 use std::thread;
 use futures::executor::block_on;
+use render::{Renderer, RunConfig};
+use std::time::Duration;
 use ExplorerMovement::*;
 use MapData::*;
 use Error::*;
@@ -55,7 +54,7 @@ impl SearchPath {
 }
 async fn initiate_search(filename: &'static str) -> Result<usize,Error> {
     let mut room_map = WorldMap::new(filename)?;
-    room_map.redraw_screen()?;
+    room_map.redraw_screen(&mut *RunConfig::from_args().renderer(Duration::from_millis(0)))?;
     let mut quadrant_alleys = Vec::new();
     for quadrant_entrance in room_map.find_entrances()? {
         let alleys = match room_map.find_all_items(0, quadrant_entrance) {
@@ -401,38 +400,24 @@ impl WorldMap {
         });
         Ok(new_world)
     }
-    fn draw_location(&self, loc: Location) -> Result<(),Error> {
-        set_cursor_loc(loc.0, loc.1);
+    fn draw_location(&self, screen: &mut dyn Renderer, loc: Location) -> Result<(),Error> {
         let map_item = match self.data.get(&loc) {
             None => ' ',
             Some(data) => data.to_char(),
         };
-        print_char(map_item);
+        screen.draw_cell(loc.0, loc.1, map_item, None);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
+    fn redraw_screen(&self, screen: &mut dyn Renderer) -> Result<(),Error> {
+        screen.clear();
         for (loc, _) in &self.data {
-            self.draw_location(*loc)?;
+            self.draw_location(screen, *loc)?;
         }
-        println!("");
+        screen.flush_frame();
+        screen.finish();
         Ok(())
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-fn print_char(ch: char) {
-    print!("{}",ch);
-    stdout().flush().unwrap();
-}
-fn set_cursor_loc(y:usize,x:usize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
 
 // #[test]
 // fn test_ex1() -> Result<(),Error> {
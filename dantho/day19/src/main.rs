@@ -1,13 +1,7 @@
 /// tps://adventofcode.com/2019/day/19
-const ESC_CLS: &'static str = "\x1B[2J";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
 
 mod intcode;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::collections::BTreeMap;
@@ -21,25 +15,19 @@ use DroidMovement::*;
 use MapData::*;
 use Error::*;
 use std::time::Duration;
+use render::{Renderer, RunConfig};
 
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 1000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = ::intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
         prog_orig.append(&mut extra_space);
     };
-    let (affected_points, part2) = match block_on(boot_intcode_and_droid(prog_orig.clone())) {
+    let screen = RunConfig::from_args().renderer(Duration::from_millis(0));
+    let (affected_points, part2) = match block_on(boot_intcode_and_droid(prog_orig.clone(), screen)) {
         Ok(result) => result,
         Err(e) => return Err(e),
     };
@@ -48,19 +36,20 @@ fn main() -> Result<(),Error> {
     println!("Part 2: TBD {}", part2 );
     Ok(())
 }
-async fn boot_intcode_and_droid(prog: Vec<isize>) -> Result<(usize,usize),Error> {
+async fn boot_intcode_and_droid(prog: Vec<isize>, screen: Box<dyn Renderer>) -> Result<(usize,usize),Error> {
     const BUFFER_SIZE: usize = 10;
     let (droid_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, droid_rx) = channel::<isize>(BUFFER_SIZE);
     let mut computer = intcode::Intcode::new(prog, computer_rx, computer_tx);
-    let droid = droid_run(droid_rx, droid_tx);
+    let droid = droid_run(droid_rx, droid_tx, screen);
     let (_computer_return,droid_response) = join!(computer.run(), droid); // , computer_snooper.monitor(), droid_snooper.monitor()
     droid_response
 }
-async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usize),Error> {
-    let mut droid = Droid::new(rx, tx);
+async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Result<(usize,usize),Error> {
+    let mut droid = Droid::new(rx, tx, screen);
     droid.explore().await?;
     droid.explored_world.redraw_screen()?;
+    droid.explored_world.screen.finish();
     let tractor_beam_count = droid.explored_world.data.iter().fold(0, |cnt, ((_,_), m)| {
         cnt + if m == &TractorBeam {1} else {0}
     });
@@ -72,6 +61,12 @@ enum Error {
     DroidComms {msg: String},
     MapAssertFail {msg: String},
     MapOriginWrong {msg: String},
+    ProgramLoad {internal: ::intcode::Error},
+}
+impl From<::intcode::Error> for Error {
+    fn from(internal: ::intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
@@ -81,7 +76,6 @@ enum MapData {
 // See https://jrgraphix.net/r/Unicode/2700-27BF for Dingbats in unicode
 impl MapData {
     fn to_str(&self) -> &'static str {
-        print("\x1B[56m"); // SIDE EFFECT - print white control chars (default color)
         match *self {
             Empty => ".",
             TractorBeam => "#",
@@ -147,12 +141,13 @@ impl TryFrom<isize> for DroidStatus {
 struct WorldMap {
     origin: (usize,usize),
     data: BTreeMap<(usize,usize), MapData>,
+    screen: Box<dyn Renderer>,
 }
 impl WorldMap {
-    fn new() -> Self {
+    fn new(screen: Box<dyn Renderer>) -> Self {
         let origin = (665,370);
         let data = BTreeMap::new();
-        WorldMap {origin, data}
+        WorldMap {origin, data, screen}
     }
     fn is_known(&self, loc: &(usize,usize)) -> bool {
         self.data.contains_key(loc)
@@ -168,25 +163,24 @@ impl WorldMap {
         }
         Ok(())
     }
-    fn draw_location(&self, loc: (usize,usize)) -> Result<(),Error> {
+    fn draw_location(&mut self, loc: (usize,usize)) -> Result<(),Error> {
         if loc.0 < self.origin.0 || loc.1 < self.origin.1 {
             return Err(MapOriginWrong {
                 msg: format!("Map loc {:?} is lower than origin at {:?}", loc, self.origin)})}
-        set_cursor_loc(loc.0 - self.origin.0, loc.1 - self.origin.1);
         let map_item = match self.data.get(&loc) {
             None => " ",
             Some(data) => data.to_str(),
         };
-        print(map_item);
+        self.screen.draw_text(loc.0 - self.origin.0, loc.1 - self.origin.1, map_item);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        for (loc, _) in &self.data {
-            self.draw_location(*loc)?;
+    fn redraw_screen(&mut self) -> Result<(),Error> {
+        self.screen.clear();
+        let locations: Vec<_> = self.data.keys().cloned().collect();
+        for loc in locations {
+            self.draw_location(loc)?;
         }
+        self.screen.flush_frame();
         Ok(())
     }
     fn update_origin(&mut self, location:(usize,usize)) -> Result<(),Error> {
@@ -201,7 +195,6 @@ impl WorldMap {
         }
         if redraw_required {
             self.redraw_screen()?;
-            set_cursor_loc(20, 20);
         }
         Ok(())
     }
@@ -219,23 +212,6 @@ impl WorldMap {
         on_screen
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-// fn print_ch(ch: char) {
-//     print!("{}",ch);
-//     stdout().flush().unwrap();
-// }
-fn set_cursor_loc(y:usize,x:usize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-// fn set_color(color:u8) {
-//     print(
-//         &format!("\x1B[{}m", 41 + color)
-//     );
-// }
 struct Droid {
     explored_world: WorldMap,
     droid_location: (usize,usize),
@@ -243,8 +219,8 @@ struct Droid {
     tx: Sender<isize>,
 }
 impl Droid {
-    fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
-        let mut explored_world = WorldMap::new();
+    fn new(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Self {
+        let mut explored_world = WorldMap::new(screen);
         let droid_location: (usize,usize) = (0,0);
         Droid { explored_world, droid_location, rx, tx }
     }
@@ -288,10 +264,6 @@ impl Droid {
         }.boxed()
     }
     async fn move_droid(&mut self, y: usize, x: usize) -> Result<DroidStatus,Error> {
-        // Slow things down for debug or visualization
-        // ESPECIALLY at start
-        let delay = Duration::from_millis(0);
-        std::thread::sleep(delay);
         // Send x of movement command to Droid's Intcode Computer
         if let Err(_) = self.tx.send(x as isize).await {
             return Err(Error::DroidComms { msg:format!("Droid output channel failure.  The following data is being discarded:\n   {:?}", x) });
@@ -1,7 +1,4 @@
 /// https://adventofcode.com/2019/day/20
-const ESC_CLS: &'static str = "\x1B[2J";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 const DBG: bool = false;
 const DBGG: bool = false; // More G's, more verbosity
 const INFINITY: usize = std::usize::MAX/1_000_000_000_000_000_000 * 1_000_000_000_000_000_000 + 123_456; // BIG number! But also recognizable as "special"
@@ -11,7 +8,7 @@ const STACK_SIZE: usize = 400 * 1024 * 1024;
 
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
+use std::io::BufReader;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::collections::{BTreeMap, HashSet};
@@ -19,6 +16,8 @@ use CardinalDirection::*;
 use MapData::*;
 use Error::*;
 use std::thread;
+use render::{Renderer, RunConfig};
+use std::time::Duration;
 
 type Location = (usize,usize);
 
@@ -44,7 +43,7 @@ fn run() -> Result<(),Error> {
 // How many steps does it take to get from the open tile marked AA to the open tile marked ZZ?
 fn process_part2(filename: &'static str) -> Result<usize,Error> {
     let mut donut_map = DonutMap::new(filename)?;
-    donut_map.redraw_screen()?;
+    donut_map.redraw_screen(&mut *RunConfig::from_args().renderer(Duration::from_millis(0)))?;
     if DBG {
         for p in &donut_map.portals {
             match p {
@@ -143,26 +142,21 @@ impl DonutMap {
             if p.name == name {Some(p)} else {None}
         }).collect()
     }
-    fn draw_location(&self, loc: Location) -> Result<(),Error> {
-        set_cursor_loc(loc.0, loc.1);
+    fn draw_location(&self, screen: &mut dyn Renderer, loc: Location) -> Result<(),Error> {
         let map_item = match self.map_data(0).get(&loc) {
             None => ' ',
             Some(data) => data.to_char(),
         };
-        print_char(map_item);
+        screen.draw_cell(loc.0, loc.1, map_item, None);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        let mut prev_column = 0;
+    fn redraw_screen(&self, screen: &mut dyn Renderer) -> Result<(),Error> {
+        screen.clear();
         for (loc, _) in &self.maps[0] {
-            if loc.1 < prev_column {println!("");}
-            prev_column = loc.1;
-            self.draw_location(*loc)?;
+            self.draw_location(screen, *loc)?;
         }
-        println!("");
+        screen.flush_frame();
+        screen.finish();
         Ok(())
     }
     fn read_initial_map(filename: &'static str) -> Result<DonutMap,Error> {
@@ -567,23 +561,6 @@ impl DonutMap {
         }
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-fn print_char(ch: char) {
-    print!("{}",ch);
-    stdout().flush().unwrap();
-}
-fn set_cursor_loc(y:usize,x:usize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-fn set_color(color:u8) {
-    print(
-        &format!("\x1B[{}m", 41 + color)
-    );
-}
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
     Wall,
@@ -1,13 +1,7 @@
 /// tps://adventofcode.com/2019/day/19
-const ESC_CLS: &'static str =  ""; //"\x1B[2J";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
 
 mod intcode;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
 use std::convert::{TryFrom};
 use std::fmt::Debug;
 use std::collections::BTreeMap;
@@ -19,25 +13,18 @@ use futures::future::BoxFuture; // https://rust-lang.github.io/async-book/07_wor
 use MapData::*;
 use Error::*;
 use std::time::Duration;
+use render::{Renderer, RunConfig};
 
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 3000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = ::intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
         prog_orig.append(&mut extra_space);
     };
-    let (affected_points, part2) = match block_on(boot_intcode_and_droid(prog_orig.clone())) {
+    let (affected_points, part2) = match block_on(boot_intcode_and_droid(prog_orig.clone(), RunConfig::from_args())) {
         Ok(result) => result,
         Err(e) => return Err(e),
     };
@@ -46,18 +33,19 @@ fn main() -> Result<(),Error> {
     println!("Part 2: TBD {}", part2 );
     Ok(())
 }
-async fn boot_intcode_and_droid(prog: Vec<isize>) -> Result<(usize,usize),Error> {
+async fn boot_intcode_and_droid(prog: Vec<isize>, config: RunConfig) -> Result<(usize,usize),Error> {
     const BUFFER_SIZE: usize = 10;
     let (droid_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, droid_rx) = channel::<isize>(BUFFER_SIZE);
     let mut computer = intcode::Intcode::new(prog, computer_rx, computer_tx);
-    let droid = droid_run(droid_rx, droid_tx);
+    let droid = droid_run(droid_rx, droid_tx, config);
     let (_computer_return,droid_response) = join!(computer.run(), droid); // , computer_snooper.monitor(), droid_snooper.monitor()
     droid_response
 }
-async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usize),Error> {
-    let mut droid = Droid::new(rx, tx);
+async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>, config: RunConfig) -> Result<(usize,usize),Error> {
+    // A pause after each batch of responses
     let delay = Duration::from_millis(300);
+    let mut droid = Droid::new(rx, tx, config.renderer(delay));
     // droid.program_springdroid("OR D J\nNOT C T\nAND T J\nAND H J\nNOT A T\nOR T J\nNOT E T\nAND A T\nAND C T\nAND D T\nAND F T\nAND G T\nAND H T\nOR T J\nRUN\n").await?;
     // droid.program_springdroid("OR D J\nNOT C T\nAND T J\nAND H J\nNOT A T\nOR T J\nNOT G T\nAND B T\nAND F T\nAND H T\nAND I T\nOR T J\nAND D J\nNOT A T\nOR T J\nRUN\n").await?;
     // equiv A: droid.program_springdroid("OR D J\nNOT C T\nAND T J\nAND E J\nOR D J\nNOT C T\nAND T J\nAND H J\nNOT A T\nOR T J\nRUN\n").await?;
@@ -69,187 +57,187 @@ async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usiz
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?; 
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
-    std::thread::sleep(delay); print(ESC_CLS);
+    droid.pause();
     droid.fetch_response().await?;
     droid.fetch_response().await?;
     droid.fetch_response().await?;
@@ -257,6 +245,7 @@ async fn droid_run(rx: Receiver<isize>, tx: Sender<isize>) -> Result<(usize,usiz
     droid.fetch_response().await?;
     droid.fetch_animation_frame().await?;
     droid.explored_world.redraw_screen()?;
+    droid.explored_world.screen.finish();
     let hull_damage = 0;
     Ok((hull_damage,0))
 }
@@ -266,6 +255,12 @@ enum Error {
     IllegalMapData {val: isize},
     DroidComms {msg: String},
     MapOriginWrong {msg: String},
+    ProgramLoad {internal: ::intcode::Error},
+}
+impl From<::intcode::Error> for Error {
+    fn from(internal: ::intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
@@ -368,12 +363,13 @@ impl TryFrom<isize> for DroidStatus {
 struct WorldMap {
     origin: (usize,usize),
     data: BTreeMap<(usize,usize), MapData>,
+    screen: Box<dyn Renderer>,
 }
 impl WorldMap {
-    fn new() -> Self {
+    fn new(screen: Box<dyn Renderer>) -> Self {
         let origin = (0,0);
         let data = BTreeMap::new();
-        WorldMap {origin, data}
+        WorldMap {origin, data, screen}
     }
     // fn is_known(&self, loc: &(usize,usize)) -> bool {
     //     self.data.contains_key(loc)
@@ -389,26 +385,25 @@ impl WorldMap {
         }
         Ok(())
     }
-    fn draw_location(&self, loc: (usize,usize)) -> Result<(),Error> {
+    fn draw_location(&mut self, loc: (usize,usize)) -> Result<(),Error> {
         if loc.0 < self.origin.0 || loc.1 < self.origin.1 {
             return Err(MapOriginWrong {
                 msg: format!("Map loc {:?} is lower than origin at {:?}", loc, self.origin)})}
-        set_cursor_loc(loc.0 - self.origin.0, loc.1 - self.origin.1);
         let map_item = match self.data.get(&loc) {
             None => " ",
             Some(data) => data.to_str(),
         };
-        print(map_item);
+        self.screen.draw_text(loc.0 - self.origin.0, loc.1 - self.origin.1, map_item);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
+    fn redraw_screen(&mut self) -> Result<(),Error> {
         println!("screen size is {} total chars", self.data.len() );
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        for (loc, _) in &self.data {
-            self.draw_location(*loc)?;
+        self.screen.clear();
+        let locations: Vec<_> = self.data.keys().cloned().collect();
+        for loc in locations {
+            self.draw_location(loc)?;
         }
+        self.screen.flush_frame();
         Ok(())
     }
     // fn update_origin(&mut self, location:(usize,usize)) -> Result<(),Error> {
@@ -441,33 +436,20 @@ impl WorldMap {
     //     on_screen
     // }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-// fn print_ch(ch: char) {
-//     print!("{}",ch);
-//     stdout().flush().unwrap();
-// }
-fn set_cursor_loc(y:usize,x:usize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-// fn set_color(color:u8) {
-//     print(
-//         &format!("\x1B[{}m", 41 + color)
-//     );
-// }
 struct Droid {
     explored_world: WorldMap,
     rx: Receiver<isize>,
     tx: Sender<isize>,
 }
 impl Droid {
-    fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
-        let explored_world = WorldMap::new();
+    fn new(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Self {
+        let explored_world = WorldMap::new(screen);
         Droid { explored_world, rx, tx }
     }
+    // Hold what's been printed so far for a frame, paced (and counted against --frames) by the renderer
+    fn pause(&mut self) {
+        self.explored_world.screen.flush_frame();
+    }
     // See https://rust-lang.github.io/async-book/07_workarounds/05_recursion.html 
     //    for explanation of fn syntax and async block usage
     fn fetch_animation_frame<'a>(&'a mut self) -> BoxFuture<'a, Result<(),Error>> {
@@ -525,10 +507,6 @@ impl Droid {
         }.boxed()
     }
     async fn program_springdroid(&mut self, prog_as_ascii: &str) -> Result<isize,Error> {
-        // Slow things down for debug or visualization
-        // ESPECIALLY at start
-        let delay = Duration::from_millis(10);
-        std::thread::sleep(delay);
         // fetch Intcode prompt
         print!("Incode prompt> '");
         loop{
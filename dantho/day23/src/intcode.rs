@@ -9,6 +9,12 @@ use futures::channel::mpsc::{Sender,Receiver};
 pub enum Error {
     IllegalOpcode {code: isize},
     ComputerComms {msg: String},
+    ProgramLoad {internal: ::intcode::Error},
+}
+impl From<::intcode::Error> for Error {
+    fn from(internal: ::intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 // Intcode Computer
 #[derive(Debug)]
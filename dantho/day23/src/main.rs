@@ -4,9 +4,6 @@ mod intcode;
 use intcode::Error;
 use intcode::Error::*;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 use futures::prelude::*;
 use futures::select;
 use futures::channel::mpsc::{channel,Sender,Receiver};
@@ -22,15 +19,7 @@ use std::time::Duration;
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 3000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = ::intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
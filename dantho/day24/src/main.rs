@@ -1,9 +1,8 @@
 /// https://adventofcode.com/2019/day/24
-const ESC_CLS: &'static str = "\x1B[2J";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 
 use std::collections::BTreeMap;
-use std::io::{stdout,Write};
+use std::time::Duration;
+use render::{Renderer, RunConfig};
 
 fn main() {
     // let initial_map = EXAMPLE;
@@ -18,11 +17,13 @@ fn main() {
             }
         }
     }
-    print(ESC_CURSOR_OFF);
+    // A frame a minute, 10ms apart unless --fps, --enter or --headless
+    let mut screen = RunConfig::from_args().renderer(Duration::from_millis(10));
     for min in 0..=200 {
-        print(ESC_CLS);
-        println!("\nMin: {}", min);
-        print_screen(&lifemaps);
+        screen.clear();
+        screen.draw_text(1, 0, &format!("Min: {}", min));
+        draw_screen(&mut *screen, &lifemaps);
+        screen.flush_frame();
         // create new life!
         let mut blanklayer = lifemaps[0].clone();
         clear_layer(&mut blanklayer);
@@ -30,8 +31,8 @@ fn main() {
         lifemaps.push(blanklayer); // add blank to end
         preprocess(&mut lifemaps);
         reproduce(&mut lifemaps);
-        std::thread::sleep(std::time::Duration::from_millis(10));
     }
+    screen.finish();
 }
 fn digit(v: u32, d: u32) -> u32 {
     if d == 0 {
@@ -126,35 +127,29 @@ fn life_count(lifemaps: &Vec<BTreeMap<(usize,usize),u32>>) -> u32 {
     }
     total
 }
-fn print_screen(lifemaps: &Vec<BTreeMap<(usize,usize),u32>>) {
+// The middle layer, below the minute count
+fn draw_screen(screen: &mut dyn Renderer, lifemaps: &Vec<BTreeMap<(usize,usize),u32>>) {
+    const TOP: usize = 3;
     let middle = (lifemaps.len()-1)/2;
     let lifemap = &lifemaps[middle];
     {
     // for lifemap in lifemaps {
-        let mut last_row = 1;
+        let mut last_row = 0;
         let mut center_value = -1;
         for ((row,col), life_cnt) in lifemap {
             // if *row == 0 || *row == 6 {continue;} // do not print border
             // if *col == 0 || *col == 6 {continue;} // do not print border
-            if *row != last_row {
-                println!("");
-            }
             if (3,3) == (*row,*col) {
                 center_value = *life_cnt as isize;
-                print!("?");
+                screen.draw_cell(TOP + row, *col, '?', None);
             } else {
-                print!("{}", if *life_cnt > 0 {'#'} else {'.'});
+                screen.draw_cell(TOP + row, *col, if *life_cnt > 0 {'#'} else {'.'}, None);
             }
             last_row = *row;
         }
-        println!("");
-        println!("Center value is {}", center_value);
+        screen.draw_text(TOP + last_row + 1, 0, &format!("Center value is {}", center_value));
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
 
 const EXAMPLE: &'static str = r#" 
 .......
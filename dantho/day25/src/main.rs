@@ -1,13 +1,8 @@
 /// https://adventofcode.com/2019/day/17
-const ESC_CLS: &'static str = "\x1B[2J";
-// const ESC_CURSOR_ON: &'static str = "\x1B[?25h";
-const ESC_CURSOR_OFF: &'static str = "\x1B[?25l";
 
 mod intcode;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Write, stdout};
+use std::time::Duration;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::collections::BTreeMap;
@@ -19,27 +14,21 @@ use DroidMovement::*;
 use DroidCommand::*;
 use MapData::*;
 use Error::*;
+use render::{Renderer, RunConfig};
 
 type Location = (isize,isize);
 
 fn main() -> Result<(),Error> {
     const PROG_MEM_SIZE: usize = 6000;
     let filename = "input.txt";
-    let fd = File::open(filename).expect(&format!("Failure opening {}", filename));
-    let buf = BufReader::new(fd);
-    let mut prog_orig = Vec::new();
-    buf.lines().for_each(|line| {
-        line.unwrap().split(',').for_each(|numstr| {
-            let num = numstr.parse::<isize>().unwrap();
-            prog_orig.push(num);
-        });
-    });
+    let mut prog_orig = ::intcode::program::load(filename)?;
     // Add some empty space for code growth
     if prog_orig.len() < PROG_MEM_SIZE {
         let mut extra_space = vec![0; PROG_MEM_SIZE - prog_orig.len()];
         prog_orig.append(&mut extra_space);
     };
-    let (part1,part2) = match block_on(boot_intcode_and_droid(prog_orig.clone())) {
+    let screen = RunConfig::from_args().renderer(Duration::from_millis(0));
+    let (part1,part2) = match block_on(boot_intcode_and_droid(prog_orig.clone(), screen)) {
         Ok(result) => result,
         Err(e) => return Err(e),
     };
@@ -49,20 +38,20 @@ fn main() -> Result<(),Error> {
     // println!("Part 2: xxx is {}", xxx);
     Ok(())
 }
-async fn boot_intcode_and_droid(prog: Vec<isize>) -> Result<(isize,isize),Error> {
+async fn boot_intcode_and_droid(prog: Vec<isize>, screen: Box<dyn Renderer>) -> Result<(isize,isize),Error> {
     const BUFFER_SIZE: usize = 10;
     let (droid_tx, computer_rx) = channel::<isize>(BUFFER_SIZE);
     let (computer_tx, droid_rx) = channel::<isize>(BUFFER_SIZE);
     let unhacked_program = prog.clone();
     // No hacks for Part 1
     let computer = intcode::intcode_run(unhacked_program, computer_rx, computer_tx);
-    let droid = droid_run_part1(droid_rx, droid_tx);
+    let droid = droid_run_part1(droid_rx, droid_tx, screen);
     let (_computer_return,droid_response_part1) = join!(computer, droid); // , computer_snooper.monitor(), droid_snooper.monitor()
 
     Ok((droid_response_part1.unwrap(), 0))
 }
-async fn droid_run_part1(rx: Receiver<isize>, tx: Sender<isize>) -> Result<isize,Error> {
-    let mut droid = Droid::new(rx, tx);
+async fn droid_run_part1(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Result<isize,Error> {
+    let mut droid = Droid::new(rx, tx, screen);
     let commands = vec![
         // Starting in '== Hull Breach =='
         // Move {dir: } // ==  ==
@@ -224,6 +213,12 @@ enum Error {
     DroidComms {msg: String},
     MapOriginWrong {msg: String},
     MapAssertFail {msg: String},
+    ProgramLoad {internal: ::intcode::Error},
+}
+impl From<::intcode::Error> for Error {
+    fn from(internal: ::intcode::Error) -> Self {
+        Error::ProgramLoad {internal}
+    }
 }
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum MapData {
@@ -232,7 +227,6 @@ enum MapData {
 // See https://jrgraphix.net/r/Unicode/2700-27BF for Dingbats in unicode
 impl MapData {
     fn to_str(&self) -> &'static str {
-        print("\x1B[56m"); // SIDE EFFECT - print white control chars (default color)
         match *self {
             Empty => "•",
         }
@@ -285,8 +279,8 @@ struct Droid {
     tx: Sender<isize>,
 }
 impl Droid {
-    fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
-        let santas_ship = WorldMap::new();
+    fn new(rx: Receiver<isize>, tx: Sender<isize>, screen: Box<dyn Renderer>) -> Self {
+        let santas_ship = WorldMap::new(screen);
         let present_location = santas_ship.origin;
         Droid { santas_ship, present_location, rx, tx }
     }
@@ -356,50 +350,32 @@ impl DroidMovement {
 struct WorldMap {
     origin: Location,
     data: BTreeMap<Location, MapData>,
+    screen: Box<dyn Renderer>,
 }
 impl WorldMap {
-    fn new() -> Self {
+    fn new(screen: Box<dyn Renderer>) -> Self {
         let origin = (-2,-2);
         let data = BTreeMap::new();
-        WorldMap {origin, data}
+        WorldMap {origin, data, screen}
     }
-    fn draw_position(&self, pos: Location) -> Result<(),Error> {
+    fn draw_position(&mut self, pos: Location) -> Result<(),Error> {
         if pos.0 < self.origin.0 || pos.1 < self.origin.1 {
             return Err(MapOriginWrong {
                 msg: format!("Map pos {:?} is lower than origin at {:?}", pos, self.origin)})}
-        set_cursor_pos(pos.0 - self.origin.0, pos.1 - self.origin.1);
         let map_item = match self.data.get(&pos) {
             None => " ",
             Some(data) => data.to_str(),
         };
-        print(map_item);
+        self.screen.draw_text((pos.0 - self.origin.0) as usize, (pos.1 - self.origin.1) as usize, map_item);
         Ok(())
     }
-    fn redraw_screen(&self) -> Result<(),Error> {
-        print(ESC_CLS); // clear screen, reset cursor
-        print(ESC_CURSOR_OFF); // Turn OFF cursor
-        // print(ESC_CURSOR_ON); // Turn ON cursor
-        for (pos, _) in &self.data {
-            self.draw_position(*pos)?;
+    fn redraw_screen(&mut self) -> Result<(),Error> {
+        self.screen.clear();
+        let positions: Vec<_> = self.data.keys().cloned().collect();
+        for pos in positions {
+            self.draw_position(pos)?;
         }
-        println!("");
+        self.screen.flush_frame();
         Ok(())
     }
 }
-fn print(s: &str) {
-    print!("{}",s);
-    stdout().flush().unwrap();
-}
-fn set_cursor_pos(y:isize,x:isize) {
-    print!("\x1B[{};{}H", y+1, x+1);
-    stdout().flush().unwrap();
-}
-// fn print_ch(ch: char) {
-//     print!("{}",ch);
-//     stdout().flush().unwrap();
-// }
-// fn set_color(color:u8) {
-//     print(
-//         &format!("\x1B[{}m", 41 + color)
-//     );
-// }
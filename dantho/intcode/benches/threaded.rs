@@ -0,0 +1,44 @@
+//! Threaded code against the interpreter, on programs from the puzzles:
+//! day19's beam probe, a fresh run per point of the 50x50 grid, and day09's one long run.
+//! Plain timings, no harness: `cargo bench --bench threaded`
+
+use std::time::{Duration, Instant};
+use intcode::{program, run_batch, Error};
+use intcode::threaded::Threaded;
+
+fn main() -> Result<(), Error> {
+    let day19 = program::load(concat!(env!("CARGO_MANIFEST_DIR"), "/../day19/input.txt"))?;
+    let points: Vec<[isize; 2]> = (0..50).flat_map(|y| (0..50).map(move |x| [x, y])).collect();
+    let threaded = Threaded::new(&day19);
+    compare("day19, 2500 probes",
+        || points.iter().map(|point| Ok(run_batch(day19.clone(), point)?.1)).collect(),
+        || points.iter().map(|point| Ok(threaded.run(point)?.1)).collect())?;
+
+    let day09 = program::load(concat!(env!("CARGO_MANIFEST_DIR"), "/../day09/input.txt"))?;
+    compare("day09 part 2",
+        || Ok(vec![run_batch(day09.clone(), &[2])?.1]),
+        || Ok(vec![Threaded::new(&day09).run(&[2])?.1]))?;
+    Ok(())
+}
+// Best of a few runs of each, after checking that both give the same outputs
+fn compare<I, T>(name: &str, interpreted: I, threaded: T) -> Result<(), Error>
+    where I: Fn() -> Result<Vec<Vec<isize>>, Error>, T: Fn() -> Result<Vec<Vec<isize>>, Error>
+{
+    const ROUNDS: usize = 5;
+    if interpreted()? != threaded()? {
+        return Err(Error::BadInput {msg: format!("{}: threaded outputs differ", name)});
+    }
+    let best = |f: &dyn Fn() -> Result<Vec<Vec<isize>>, Error>| -> Result<Duration, Error> {
+        let mut best = Duration::from_secs(u64::MAX);
+        for _ in 0..ROUNDS {
+            let start = Instant::now();
+            f()?;
+            best = best.min(start.elapsed());
+        }
+        Ok(best)
+    };
+    let (slow, fast) = (best(&interpreted)?, best(&threaded)?);
+    println!("{:<20} run_batch {:>10.2?}   threaded {:>10.2?}   {:.1}x",
+        name, slow, fast, slow.as_secs_f64() / fast.as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! Cheat search, in the style of Cheat Engine, for finding game state in a running program.
+//! Take a snapshot of memory each frame and narrow the candidate addresses with filters
+//! ("changed", "unchanged", "increased", "decreased", "=N") until the ball's x, the paddle's x or
+//! the score is pinned down.  Then poke it, or freeze it so it gets rewritten every frame.
+//!
+//! A frame starts whenever the computer is handed an input: attach `Cheats` with `Intcode::set_cheats`
+//! and, as each input arrives, the computer stores it, then applies pending pokes and frozen values
+//! and takes a snapshot.  (So freezing the address an input lands at overrides the input.)
+//! So a poke made before sending input N lands before the program acts on input N, and snapshot N
+//! shows memory as it was while the screen for input N was being looked at.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Filter {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Equals(isize),
+}
+impl Filter {
+    /// "changed", "unchanged", "increased", "decreased" or "=N"
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "changed" => Some(Filter::Changed),
+            "unchanged" => Some(Filter::Unchanged),
+            "increased" => Some(Filter::Increased),
+            "decreased" => Some(Filter::Decreased),
+            _ => text.strip_prefix('=').and_then(|n| n.parse().ok()).map(Filter::Equals),
+        }
+    }
+    fn keeps(&self, before: isize, now: isize) -> bool {
+        match self {
+            Filter::Changed => now != before,
+            Filter::Unchanged => now == before,
+            Filter::Increased => now > before,
+            Filter::Decreased => now < before,
+            Filter::Equals(n) => now == *n,
+        }
+    }
+}
+#[derive(Debug,Clone)]
+pub struct CheatSearch {
+    candidates: Vec<usize>,
+    previous: Vec<isize>,
+}
+impl CheatSearch {
+    /// Every address in `snapshot` starts out as a candidate
+    pub fn new(snapshot: &[isize]) -> Self {
+        CheatSearch { candidates: (0..snapshot.len()).collect(), previous: snapshot.to_vec() }
+    }
+    /// Keep the candidates whose change since the last snapshot passes `filter`.
+    /// Returns how many are left.
+    pub fn refine(&mut self, snapshot: &[isize], filter: Filter) -> usize {
+        let previous = &self.previous;
+        let value = |memory: &[isize], addr: usize| *memory.get(addr).unwrap_or(&0);
+        self.candidates.retain(|addr| filter.keeps(value(previous, *addr), value(snapshot, *addr)));
+        self.previous = snapshot.to_vec();
+        self.candidates.len()
+    }
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+    /// The single remaining candidate, once the search has narrowed that far
+    pub fn found(&self) -> Option<usize> {
+        match self.candidates.as_slice() {
+            [addr] => Some(*addr),
+            _ => None,
+        }
+    }
+}
+#[derive(Debug,Default)]
+struct CheatTable {
+    frames: usize,
+    snapshot: Vec<isize>,
+    pokes: Vec<(usize, isize)>,
+    frozen: BTreeMap<usize, isize>,
+}
+/// Shared by a running computer and whoever is cheating; clones refer to the same table
+#[derive(Debug,Clone,Default)]
+pub struct Cheats(Arc<Mutex<CheatTable>>);
+impl Cheats {
+    pub fn new() -> Self {
+        Cheats::default()
+    }
+    /// Inputs handed over so far.  The snapshot was taken as input number `frames()` arrived.
+    pub fn frames(&self) -> usize {
+        self.0.lock().unwrap().frames
+    }
+    pub fn snapshot(&self) -> Vec<isize> {
+        self.0.lock().unwrap().snapshot.clone()
+    }
+    /// Written once, as the next input arrives
+    pub fn poke(&self, addr: usize, value: isize) {
+        self.0.lock().unwrap().pokes.push((addr, value));
+    }
+    /// Written as every input arrives, from now on
+    pub fn freeze(&self, addr: usize, value: isize) {
+        self.0.lock().unwrap().frozen.insert(addr, value);
+    }
+    pub fn unfreeze(&self, addr: usize) {
+        self.0.lock().unwrap().frozen.remove(&addr);
+    }
+    // The computer's end of it, at every input
+    pub(crate) fn sync(&self, memory: &mut Vec<isize>, limit: usize) {
+        let mut table = self.0.lock().unwrap();
+        let pokes: Vec<(usize, isize)> = table.pokes.drain(..).collect();
+        for (addr, value) in pokes.into_iter().chain(table.frozen.iter().map(|(a, v)| (*a, *v))) {
+            if addr < limit {
+                if addr >= memory.len() {
+                    memory.resize(addr + 1, 0);
+                }
+                memory[addr] = value;
+            }
+        }
+        table.frames += 1;
+        table.snapshot = memory.clone();
+    }
+}
+
+#[test]
+fn test_cheat_search() {
+    // A counter at [12] that the program bumps once per frame, echoing each input (read into [13])
+    let prog = vec![1001,12,1,12, 3,13, 4,13, 1105,1,0, 0, 0,0];
+    let cheats = Cheats::new();
+    let outputs = crate::io::Collector::new();
+    let inputs = crate::io::InputIter(vec![0, 1, 2, 3, 4].into_iter());
+    let mut computer = crate::Intcode::with_io(prog.clone(), inputs, outputs.clone());
+    computer.set_cheats(Some(cheats.clone()));
+    cheats.freeze(13, 42);
+    assert!(computer.run_blocking().is_err()); // runs out of input on the 6th frame
+    // The program only ever sees the frozen value, never its input
+    assert_eq!(outputs.values(), vec![42; 5]);
+    assert_eq!(cheats.frames(), 5);
+    let snapshot = cheats.snapshot();
+    assert_eq!((snapshot[12], snapshot[13]), (5, 42));
+    let mut search = CheatSearch::new(&prog);
+    assert_eq!(search.refine(&snapshot, Filter::Increased), 2);
+    assert_eq!(search.refine(&snapshot, Filter::parse("=5").unwrap()), 1);
+    assert_eq!(search.found(), Some(12));
+}
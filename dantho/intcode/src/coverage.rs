@@ -0,0 +1,148 @@
+//! Code coverage for Intcode programs.
+//! While enabled, the computer notes every address it executes as an instruction and every
+//! address it reads or writes as data.  Coverage from several runs can be merged, and the
+//! report overlays it on the disassembly:
+//! ```text
+//! # coverage: 219 of 661 instructions executed (33.1%)
+//! ...
+//!    X         0: 109,4796                 arb #4796
+//!    -        76: 4,97                     out [97]
+//!     r       97: 0                        data 0
+//! ```
+//! X marks instructions that ran, - instructions that never did, r and w data reads and writes.
+
+use std::fs::File;
+use std::io::prelude::*;
+use crate::Error;
+use crate::disasm::{self, Hint, Line};
+
+const EXECUTED: u8 = 1; // an instruction started here
+const OPERAND: u8 = 2;  // fetched as a parameter of an executed instruction
+const READ: u8 = 4;
+const WRITTEN: u8 = 8;
+
+#[derive(Debug,Clone,Default,Eq,PartialEq)]
+pub struct Coverage {
+    flags: Vec<u8>,
+}
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage { flags: Vec::new() }
+    }
+    pub(crate) fn mark_instruction(&mut self, pc: usize, arity: usize) {
+        self.set(pc, EXECUTED);
+        for addr in pc + 1..=pc + arity {
+            self.set(addr, OPERAND);
+        }
+    }
+    pub(crate) fn mark_read(&mut self, addr: usize) {
+        self.set(addr, READ);
+    }
+    pub(crate) fn mark_written(&mut self, addr: usize) {
+        self.set(addr, WRITTEN);
+    }
+    fn set(&mut self, addr: usize, flag: u8) {
+        if addr >= self.flags.len() {
+            self.flags.resize(addr + 1, 0);
+        }
+        self.flags[addr] |= flag;
+    }
+    fn get(&self, addr: usize) -> u8 {
+        *self.flags.get(addr).unwrap_or(&0)
+    }
+    /// Fold in the coverage of another run
+    pub fn merge(&mut self, other: &Coverage) {
+        for (addr, flag) in other.flags.iter().enumerate() {
+            if *flag != 0 {
+                self.set(addr, *flag);
+            }
+        }
+    }
+    pub fn executed(&self, addr: usize) -> bool {
+        self.get(addr) & EXECUTED != 0
+    }
+    pub fn read(&self, addr: usize) -> bool {
+        self.get(addr) & READ != 0
+    }
+    pub fn written(&self, addr: usize) -> bool {
+        self.get(addr) & WRITTEN != 0
+    }
+    // Only ever touched as data, never fetched as part of an instruction
+    fn data_only(&self, addr: usize) -> bool {
+        let flag = self.get(addr);
+        flag & (READ | WRITTEN) != 0 && flag & (EXECUTED | OPERAND) == 0
+    }
+    /// Disassembly of `prog` (the image as loaded, before the run changed it) with coverage marks
+    pub fn report(&self, prog: &[isize]) -> String {
+        let lines = disasm::disassemble_with(prog, |addr| {
+            if self.executed(addr) {Hint::Code} else if self.data_only(addr) {Hint::Data} else {Hint::Unknown}
+        });
+        let mut listing = Vec::new();
+        let (mut code_lines, mut executed_lines, mut unreached) = (0, 0, Vec::new());
+        for line in &lines {
+            let span = line.addr()..line.addr() + line.size();
+            let read = span.clone().any(|addr| self.read(addr));
+            let written = span.clone().any(|addr| self.written(addr));
+            let exec = match line {
+                Line::Code(_) if self.executed(line.addr()) => 'X',
+                Line::Code(_) => '-',
+                Line::Data {..} => ' ',
+            };
+            if let Line::Code(_) = line {
+                code_lines += 1;
+                if exec == 'X' {
+                    executed_lines += 1;
+                } else {
+                    // Extend the current unreached stretch, or start a new one
+                    match unreached.last_mut() {
+                        Some((_, end)) if *end == line.addr() => *end = span.end,
+                        _ => unreached.push((span.start, span.end)),
+                    }
+                }
+            }
+            let marks = format!("{}{}{}", exec, if read {'r'} else {' '}, if written {'w'} else {' '});
+            listing.push(format!("   {}  {}", marks, disasm::format_line(prog, line)));
+        }
+        let beyond = (prog.len()..self.flags.len()).filter(|addr| self.get(*addr) != 0).count();
+        let mut report = Vec::new();
+        report.push(format!("# coverage: {} of {} instructions executed ({:.1}%)",
+            executed_lines, code_lines, 100.0 * executed_lines as f64 / code_lines.max(1) as f64));
+        report.push(format!("# data: {} addresses read, {} written, {} used beyond the {}-word image",
+            (0..self.flags.len()).filter(|addr| self.read(*addr)).count(),
+            (0..self.flags.len()).filter(|addr| self.written(*addr)).count(),
+            beyond, prog.len()));
+        let stretches: Vec<String> = unreached.iter().map(|(start, end)| format!("{}..{}", start, end - 1)).collect();
+        report.push(format!("# never reached: {}", if stretches.is_empty() {"nothing".to_string()} else {stretches.join(", ")}));
+        report.push("#  X executed  - never executed  r read as data  w written".to_string());
+        report.append(&mut listing);
+        report.join("\n") + "\n"
+    }
+    pub fn save_report(&self, filename: &str, prog: &[isize]) -> Result<(), Error> {
+        File::create(filename)
+            .and_then(|mut fd| fd.write_all(self.report(prog).as_bytes()))
+            .map_err(|e| Error::BadInput {msg: format!("Failure writing {}: {}", filename, e)})
+    }
+}
+
+#[cfg(test)]
+fn coverage_of(prog: &[isize], input: isize) -> Coverage {
+    let (mut computer, rx) = crate::Intcode::with_inputs(prog.to_vec(), &[input]).unwrap();
+    computer.set_coverage(Some(Coverage::new()));
+    computer.run_collecting(rx).unwrap();
+    computer.take_coverage().unwrap()
+}
+#[test]
+fn test_coverage_report() {
+    // Read into [12]; jump over the add when the input is non-zero; write [12]; halt
+    let prog = vec![3,12, 1005,12,9, 1101,0,0,13, 4,12, 99, 0,0];
+    let mut coverage = coverage_of(&prog, 5);
+    assert!(coverage.executed(0) && coverage.executed(2) && coverage.executed(9));
+    assert!(!coverage.executed(5) && !coverage.written(13));
+    assert!(coverage.read(12) && coverage.written(12));
+    let report = coverage.report(&prog);
+    assert!(report.contains("# coverage: 4 of 5 instructions executed"));
+    assert!(report.contains("# never reached: 5..8"));
+    coverage.merge(&coverage_of(&prog, 0));
+    assert!(coverage.executed(5) && coverage.written(13));
+    assert!(coverage.report(&prog).contains("# never reached: nothing"));
+}
@@ -0,0 +1,726 @@
+//! Intcode decompiler.
+//! The puzzle programs come out of a compiler with a few fixed habits, and recognizing them turns
+//! the disassembly back into something like source:
+//! - a call stores the return address at [rb+0], arguments at [rb+1].., then jumps;
+//! - a function starts with `arb #N`, so the caller's [rb+k] is its [rb+k-N], and it returns with
+//!   `arb #-N` and a jump through [rb+0], leaving its result in the first argument slot;
+//! - a jump backwards closes a loop, a conditional jump forwards skips an `if` body, and an
+//!   unconditional jump at the end of that body skips the `else`.
+//!
+//! Code is found by following calls from address 0, and from every `arb` that follows a return
+//! (day25 calls most of its functions through pointers).  Jumps through words no instruction writes
+//! are followed too.  Where the program rewrites an instruction's address before running it -- its
+//! way of indexing arrays -- the access is shown as `mem[..]`.  What doesn't fit is left as `goto`.
+//! ```text
+//! intcode decompile ../day25/input.txt
+//! // called from 1248, 2547
+//! fn f1174(arg1, arg2) {
+//!     var local1, local2, local3;
+//!     local2 = mem[arg1];
+//!     ...
+//!         (*arg2)(mem[arg1 + local1], local1, local2);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use crate::OpCode;
+use crate::disasm::{self, Instruction, Line, Param};
+
+/// A function found by following calls from address 0 (which is `main`)
+#[derive(Debug,Clone)]
+pub struct Function {
+    pub entry: usize,
+    pub frame: isize,        // words reserved by the opening `arb`; the return address is at [rb-frame]
+    pub params: usize,       // frame slots the callers fill in, the rest are locals
+    pub returns: bool,       // writes its first argument slot, where callers pick up the result
+    pub callers: Vec<usize>, // addresses of the calls
+    code: BTreeMap<usize, Instruction>,
+}
+impl Function {
+    pub fn name(&self) -> String {
+        function_name(self.entry)
+    }
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.code.values()
+    }
+}
+/// Every function reachable from address 0, by entry address
+pub fn functions(prog: &[isize]) -> Vec<Function> {
+    Decompiler::new(prog).functions.into_values().collect()
+}
+/// Pseudocode for the whole program
+pub fn decompile(prog: &[isize]) -> String {
+    let decompiler = Decompiler::new(prog);
+    let mut lines = Vec::new();
+    for function in decompiler.functions.values() {
+        decompiler.emit(function, &mut lines);
+        lines.push(String::new());
+    }
+    lines.join("\n")
+}
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+enum Target {
+    Fixed(usize),
+    Via(usize, usize), // through a word that nothing writes: its address and its value
+    Dynamic(Param),
+}
+impl Target {
+    fn addr(&self) -> Option<usize> {
+        match self {
+            Target::Fixed(addr) | Target::Via(_, addr) => Some(*addr),
+            Target::Dynamic(_) => None,
+        }
+    }
+}
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+enum Flow {
+    Next,
+    Halt,
+    Return,
+    Call(Target),
+    Jump(Target, Option<Condition>),
+}
+type Condition = (Param, bool); // tested value, and whether non-zero takes the jump
+// How the body of a function is laid out, worked out before anything is printed
+#[derive(Debug,Default)]
+struct Layout {
+    loops: Vec<(usize, usize, usize)>,              // start, end, and the jump back that closes it
+    ifs: BTreeMap<usize, (usize, Option<usize>)>,   // header: end of the body, end of the else
+    separators: BTreeSet<usize>,                    // jumps over an else, printed as "} else {"
+    inverted: BTreeSet<usize>,                      // headers of an if with nothing but the else
+    breaks: BTreeSet<usize>,
+    folded: BTreeSet<usize>,                        // stores printed where their value is used
+    args: BTreeMap<usize, BTreeMap<isize, String>>, // call: argument slot -> expression
+    pointers: BTreeMap<usize, String>,              // parameter word -> the address stored there just before
+}
+struct Decompiler<'a> {
+    prog: &'a [isize],
+    written: BTreeSet<usize>,
+    functions: BTreeMap<usize, Function>,
+}
+impl<'a> Decompiler<'a> {
+    fn new(prog: &'a [isize]) -> Self {
+        // Words some instruction stores to -- everything else keeps its value, code included.
+        // A sweep of the image finds stores in data that merely looks like code, so once the code
+        // has been found, look again at just that.
+        let sweep: Vec<Instruction> = disasm::disassemble(prog).into_iter()
+            .filter_map(|line| match line {
+                Line::Code(ins) => Some(ins),
+                Line::Data {..} => None,
+            })
+            .collect();
+        let mut decompiler = Decompiler { prog, written: stores(sweep.iter(), &BTreeSet::new()), functions: BTreeMap::new() };
+        for _ in 0..4 {
+            decompiler.functions.clear();
+            decompiler.discover(&sweep);
+            let code = decompiler.functions.values().flat_map(|function| function.code.values());
+            let written = stores(code, &decompiler.written);
+            if written == decompiler.written {
+                break;
+            }
+            decompiler.written = written;
+        }
+        decompiler
+    }
+    fn discover(&mut self, sweep: &[Instruction]) {
+        // Functions called only through pointers can't be found by following calls, but the
+        // compiler puts them one after another: an `arb` straight after a return starts one.
+        let mut entries: Vec<usize> = sweep.windows(2)
+            .filter(|pair| self.flow(&pair[0]) == Flow::Return && pair[0].addr + pair[0].size() == pair[1].addr)
+            .filter(|pair| pair[1].opcode == OpCode::AdjustBase && matches!(pair[1].params[0], Param::Immediate(n) if n > 0))
+            .map(|pair| pair[1].addr)
+            .collect();
+        entries.push(0);
+        let mut calls = Vec::new(); // (caller, call, callee)
+        while let Some(entry) = entries.pop() {
+            if self.functions.contains_key(&entry) {
+                continue;
+            }
+            let mut code = BTreeMap::new();
+            let mut todo = vec![entry];
+            while let Some(addr) = todo.pop() {
+                if code.contains_key(&addr) {
+                    continue;
+                }
+                let ins = match disasm::decode(self.prog, addr) {
+                    Some(ins) => ins,
+                    None => continue,
+                };
+                let next = addr + ins.size();
+                match self.flow(&ins) {
+                    Flow::Next => todo.push(next),
+                    Flow::Halt | Flow::Return => (),
+                    Flow::Call(target) => {
+                        if let Some(callee) = target.addr() {
+                            entries.push(callee);
+                            calls.push((entry, addr, callee));
+                        }
+                        todo.push(next);
+                    },
+                    Flow::Jump(target, cond) => {
+                        todo.extend(target.addr());
+                        if cond.is_some() {
+                            todo.push(next);
+                        }
+                    },
+                }
+                code.insert(addr, ins);
+            }
+            let frame = match code.get(&entry) {
+                Some(ins) if entry != 0 && ins.opcode == OpCode::AdjustBase => match ins.params[0] {
+                    Param::Immediate(n) if n > 0 => n,
+                    _ => 0,
+                },
+                _ => 0,
+            };
+            let returns = frame > 1 && code.values().any(|ins| {
+                ins.opcode.writes_last() && ins.params.last() == Some(&Param::Relative(1 - frame))
+            });
+            self.functions.insert(entry, Function { entry, frame, params: 0, returns, callers: Vec::new(), code });
+        }
+        // A function takes as many arguments as its busiest caller sets up
+        for (caller, call, callee) in calls {
+            let stored = self.run_before(&self.functions[&caller], call).iter()
+                .filter_map(|ins| match ins.params.last() {
+                    Some(Param::Relative(slot)) if *slot > 0 && ins.opcode.writes_last() => Some(*slot as usize),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+            let function = self.functions.get_mut(&callee).unwrap();
+            function.params = function.params.max(stored.min((function.frame - 1).max(0) as usize));
+            function.callers.push(call);
+        }
+        for function in self.functions.values_mut() {
+            function.callers.sort_unstable();
+        }
+        // Nobody calls these directly, so their arguments are the slots they read before writing
+        for function in self.functions.values_mut().filter(|function| function.callers.is_empty() && function.entry != 0) {
+            let mut seen = BTreeSet::new();
+            for ins in function.code.values() {
+                for (i, param) in ins.params.iter().enumerate() {
+                    let slot = match param {
+                        Param::Relative(offset) if -function.frame < *offset && *offset < 0 => (offset + function.frame) as usize,
+                        _ => continue,
+                    };
+                    let reads = !(ins.opcode.writes_last() && i + 1 == ins.params.len());
+                    if seen.insert(slot) && reads {
+                        function.params = function.params.max(slot);
+                    }
+                }
+            }
+        }
+    }
+    fn flow(&self, ins: &Instruction) -> Flow {
+        match ins.opcode {
+            OpCode::Halt => Flow::Halt,
+            OpCode::BranchNE | OpCode::BranchEQ => {
+                let (cond, dest) = (ins.params[0], ins.params[1]);
+                let on_nonzero = ins.opcode == OpCode::BranchNE;
+                let target = self.target(ins, 1);
+                match self.constant(ins, 0) {
+                    Some(val) if (val != 0) != on_nonzero => Flow::Next,
+                    Some(_) if self.is_call(ins) => Flow::Call(target),
+                    Some(_) if dest == Param::Relative(0) => Flow::Return,
+                    Some(_) => Flow::Jump(target, None),
+                    None => Flow::Jump(target, Some((cond, on_nonzero))),
+                }
+            },
+            _ => Flow::Next,
+        }
+    }
+    // A jump straight after storing its own return address at [rb+0]
+    fn is_call(&self, ins: &Instruction) -> bool {
+        let link = ins.addr.checked_sub(4).and_then(|addr| disasm::decode(self.prog, addr));
+        let next = (ins.addr + ins.size()) as isize;
+        link.is_some_and(|link| link.params.last() == Some(&Param::Relative(0)) && constant_store(&link) == Some(next))
+    }
+    // The value of parameter `i`, if it is the same every time the instruction runs
+    fn constant(&self, ins: &Instruction, i: usize) -> Option<isize> {
+        if self.written.contains(&(ins.addr + 1 + i)) {
+            return None;
+        }
+        match ins.params[i] {
+            Param::Immediate(val) => Some(val),
+            Param::Position(addr) => {
+                let addr = usize::try_from(addr).ok().filter(|addr| !self.written.contains(addr))?;
+                self.prog.get(addr).copied()
+            },
+            Param::Relative(_) => None,
+        }
+    }
+    fn target(&self, ins: &Instruction, i: usize) -> Target {
+        let param = ins.params[i];
+        let addr = self.constant(ins, i).and_then(|val| usize::try_from(val).ok());
+        match (param, addr) {
+            (Param::Immediate(_), Some(addr)) => Target::Fixed(addr),
+            // A pointer still holding 0 is filled in at runtime
+            (Param::Position(via), Some(addr)) if addr != 0 => Target::Via(via as usize, addr),
+            _ => Target::Dynamic(param),
+        }
+    }
+    // The plain statements leading up to a call's return address store, nearest first
+    fn run_before<'f>(&self, function: &'f Function, call: usize) -> Vec<&'f Instruction> {
+        let targets = self.jump_targets(function);
+        let mut run = Vec::new();
+        let mut addr = call - 4;
+        while !targets.contains(&addr) {
+            match function.code.range(..addr).next_back() {
+                Some((_, ins)) if ins.addr + ins.size() == addr && is_statement(ins) => {
+                    run.push(ins);
+                    addr = ins.addr;
+                },
+                _ => break,
+            }
+        }
+        run
+    }
+    fn jump_targets(&self, function: &Function) -> BTreeSet<usize> {
+        function.code.values()
+            .filter_map(|ins| match self.flow(ins) {
+                Flow::Jump(target, _) => target.addr(),
+                _ => None,
+            })
+            .collect()
+    }
+    fn layout(&self, function: &Function) -> Layout {
+        let mut layout = Layout::default();
+        let names = Names::of(function);
+        let code = &function.code;
+        let jumps: Vec<(&Instruction, usize, Option<Condition>)> = code.values()
+            .filter_map(|ins| match self.flow(ins) {
+                Flow::Jump(target, cond) => target.addr().filter(|addr| code.contains_key(addr)).map(|addr| (ins, addr, cond)),
+                _ => None,
+            })
+            .collect();
+        let nests = |a: (usize, usize), b: (usize, usize)| {
+            a.1 <= b.0 || b.1 <= a.0 || (a.0 <= b.0 && b.1 <= a.1) || (b.0 <= a.0 && a.1 <= b.1)
+        };
+        // Loops first, outermost first, as long as they nest
+        let mut candidates: Vec<(usize, usize, usize)> = jumps.iter()
+            .filter(|(ins, dest, _)| *dest <= ins.addr)
+            .map(|(ins, dest, _)| (*dest, ins.addr + ins.size(), ins.addr))
+            .collect();
+        candidates.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+        for candidate in candidates {
+            if layout.loops.iter().all(|l| nests((l.0, l.1), (candidate.0, candidate.1))) {
+                layout.loops.push(candidate);
+            }
+        }
+        let innermost = |loops: &[(usize, usize, usize)], addr: usize| {
+            loops.iter().filter(|l| l.0 <= addr && addr < l.1).min_by_key(|l| l.1 - l.0).copied()
+        };
+        // Then forward jumps, outermost first: out of a loop, or around an if body
+        let mut blocks: Vec<(usize, usize)> = layout.loops.iter().map(|l| (l.0, l.1)).collect();
+        for (ins, dest, cond) in &jumps {
+            if *dest <= ins.addr || layout.separators.contains(&ins.addr) {
+                continue;
+            }
+            if innermost(&layout.loops, ins.addr).is_some_and(|l| l.1 == *dest) {
+                layout.breaks.insert(ins.addr);
+                continue;
+            }
+            let header = (ins.addr, *dest);
+            // A loop starting at the header runs the test every time round, so the if goes inside it
+            let fits = |blocks: &[(usize, usize)], block: (usize, usize)| blocks.iter().all(|b| nests(*b, block))
+                && layout.loops.iter().all(|l| l.0 != header.0 || l.1 >= block.1);
+            if cond.is_none() || !fits(&blocks, header) {
+                continue;
+            }
+            let last = code.range(..*dest).next_back().map(|(_, last)| last).filter(|last| last.addr + last.size() == *dest);
+            let skip = last.filter(|last| last.addr != ins.addr).and_then(|last| match self.flow(last) {
+                Flow::Jump(target, None) => target.addr()
+                    .filter(|end| *end > *dest && code.contains_key(end))
+                    .filter(|end| innermost(&layout.loops, last.addr).is_none_or(|l| l.1 != *end))
+                    .map(|end| (last.addr, end)),
+                _ => None,
+            });
+            match skip {
+                // An empty body: the else is the whole if, with the test turned round
+                Some((separator, end)) if separator == ins.addr + ins.size() && fits(&blocks, (ins.addr, end)) => {
+                    blocks.push((ins.addr, end));
+                    layout.separators.insert(separator);
+                    layout.folded.insert(separator);
+                    layout.inverted.insert(ins.addr);
+                    layout.ifs.insert(ins.addr, (end, None));
+                },
+                Some((separator, end)) if fits(&blocks, (*dest, end)) && fits(&blocks, (ins.addr, end)) => {
+                    blocks.extend(&[header, (*dest, end), (ins.addr, end)]);
+                    layout.separators.insert(separator);
+                    layout.ifs.insert(ins.addr, (*dest, Some(end)));
+                },
+                _ => {
+                    blocks.push(header);
+                    layout.ifs.insert(ins.addr, (*dest, None));
+                },
+            }
+        }
+        // Array access patches the address of the next instruction's parameter: show that as mem[..]
+        let targets = self.jump_targets(function);
+        // (stores to the stack in between, a call's arguments say, don't matter)
+        for ins in code.values() {
+            let mut addr = ins.addr;
+            while !targets.contains(&addr) {
+                let store = match code.range(..addr).next_back() {
+                    Some((_, store)) if store.addr + store.size() == addr && is_statement(store) => store,
+                    _ => break,
+                };
+                let word = match store.params.last() {
+                    Some(Param::Relative(_)) if store.opcode.writes_last() => {
+                        addr = store.addr;
+                        continue;
+                    },
+                    Some(Param::Position(word)) => *word as usize,
+                    _ => break,
+                };
+                let patched = (ins.addr + 1..ins.addr + ins.size()).contains(&word)
+                    && matches!(ins.params[word - ins.addr - 1], Param::Position(_))
+                    && !layout.pointers.contains_key(&word);
+                if !patched || store.opcode == OpCode::Read {
+                    break;
+                }
+                let pointer = self.expression(store, &names, &layout.pointers);
+                layout.pointers.insert(word, pointer);
+                layout.folded.insert(store.addr);
+                addr = store.addr;
+            }
+        }
+        // Arguments computed just before a call go inside it
+        for ins in code.values() {
+            let target = match self.flow(ins) {
+                Flow::Call(target) => self.call_pointer(function, ins).map_or(target, Target::Dynamic),
+                _ => continue,
+            };
+            // Calls through a pointer pass whatever was set up
+            let params = match target {
+                Target::Dynamic(_) => self.run_before(function, ins.addr).iter()
+                    .filter_map(|store| match store.params.last() {
+                        Some(Param::Relative(slot)) if *slot > 0 && store.opcode.writes_last() => Some(*slot),
+                        _ => None,
+                    })
+                    .max()
+                    .unwrap_or(0),
+                _ => target.addr().and_then(|addr| self.functions.get(&addr)).map_or(0, |callee| callee.params as isize),
+            };
+            let mut args = BTreeMap::new();
+            for store in self.run_before(function, ins.addr) {
+                let slot = match store.params.last() {
+                    Some(Param::Relative(slot)) if (1..=params).contains(slot) && !args.contains_key(slot) => *slot,
+                    _ => break,
+                };
+                let reads_frame = store.params[..store.params.len() - 1].iter().any(|p| matches!(p, Param::Relative(o) if *o >= 0));
+                if store.opcode == OpCode::Read || reads_frame {
+                    break;
+                }
+                args.insert(slot, self.expression(store, &names, &layout.pointers));
+                layout.folded.insert(store.addr);
+            }
+            layout.args.insert(ins.addr, args);
+        }
+        layout
+    }
+    fn emit(&self, function: &Function, lines: &mut Vec<String>) {
+        let names = Names::of(function);
+        let layout = self.layout(function);
+        let code = &function.code;
+        if function.callers.is_empty() && function.entry != 0 {
+            lines.push("// called through a pointer".to_string());
+        } else if !function.callers.is_empty() {
+            let callers: Vec<String> = function.callers.iter().map(|c| c.to_string()).collect();
+            lines.push(format!("// called from {}", callers.join(", ")));
+        }
+        let params: Vec<String> = (1..=function.params).map(|i| format!("arg{}", i)).collect();
+        lines.push(format!("fn {}({}) {{", function.name(), params.join(", ")));
+        let locals = (function.frame - 1).max(0) as usize - function.params;
+        if locals > 0 {
+            let locals: Vec<String> = (1..=locals).map(|i| format!("local{}", i)).collect();
+            lines.push(format!("    var {};", locals.join(", ")));
+        }
+        // Jumps that didn't become structure need somewhere to land
+        let labels: BTreeSet<usize> = code.values()
+            .filter(|ins| !layout.ifs.contains_key(&ins.addr) && !layout.separators.contains(&ins.addr))
+            .filter(|ins| !layout.breaks.contains(&ins.addr) && !layout.loops.iter().any(|l| l.2 == ins.addr))
+            .filter_map(|ins| match self.flow(ins) {
+                Flow::Jump(target, _) => target.addr(),
+                _ => None,
+            })
+            .collect();
+        let mut open: Vec<(usize, bool)> = Vec::new(); // end of each open block, and whether it is a loop
+        let indent = |open: &Vec<(usize, bool)>| "    ".repeat(open.len() + 1);
+        for ins in code.values() {
+            let addr = ins.addr;
+            // Loops are closed by their jump back, everything else where it ends
+            while let Some((end, is_loop)) = open.last() {
+                if *is_loop || *end > addr {
+                    break;
+                }
+                open.pop();
+                lines.push(format!("{}}}", indent(&open)));
+            }
+            if labels.contains(&addr) {
+                lines.push(format!("{}L{}:", "    ".repeat(open.len()), addr));
+            }
+            for l in layout.loops.iter().filter(|l| l.0 == addr) {
+                let opening = if matches!(self.flow(&code[&l.2]), Flow::Jump(_, None)) {"while (true) {"} else {"do {"};
+                lines.push(format!("{}{}", indent(&open), opening));
+                open.push((l.1, true));
+            }
+            let flow = self.flow(ins);
+            let operand = |i: usize| self.operand(ins, i, &names, &layout.pointers);
+            let statement = match flow {
+                _ if layout.folded.contains(&addr) => continue,
+                _ if addr == function.entry && function.frame > 0 => continue, // the prologue
+                _ if self.is_epilogue(function, ins) => continue,
+                _ if ins.opcode.writes_last() && ins.params.last() == Some(&Param::Relative(0)) && self.calls_next(ins) => continue,
+                Flow::Jump(_, cond) if layout.loops.iter().any(|l| l.2 == addr) => {
+                    open.pop();
+                    let closing = match cond {
+                        Some((_, on_nonzero)) => format!("}} while ({});", taken_when(operand(0), on_nonzero)),
+                        None => "}".to_string(),
+                    };
+                    lines.push(format!("{}{}", indent(&open), closing));
+                    continue;
+                },
+                Flow::Jump(_, _) if layout.separators.contains(&addr) => {
+                    let end = match layout.ifs.values().find(|(body, _)| *body == addr + ins.size()) {
+                        Some((_, Some(end))) => *end,
+                        _ => unreachable!("separator without an if"),
+                    };
+                    open.pop();
+                    lines.push(format!("{}}} else {{", indent(&open)));
+                    open.push((end, false));
+                    continue;
+                },
+                Flow::Jump(_, Some((_, on_nonzero))) if layout.ifs.contains_key(&addr) => {
+                    let runs_when = on_nonzero == layout.inverted.contains(&addr);
+                    lines.push(format!("{}if ({}) {{", indent(&open), taken_when(operand(0), runs_when)));
+                    open.push((layout.ifs[&addr].0, false));
+                    continue;
+                },
+                Flow::Jump(target, cond) => {
+                    let action = if layout.breaks.contains(&addr) {
+                        "break;".to_string()
+                    } else {
+                        match target {
+                            Target::Fixed(dest) => format!("goto L{};", dest),
+                            Target::Via(via, dest) => format!("goto L{}; // via g{}", dest, via),
+                            Target::Dynamic(_) => format!("goto *{};", operand(1)),
+                        }
+                    };
+                    match cond {
+                        Some((_, on_nonzero)) => format!("if ({}) {}", taken_when(operand(0), on_nonzero), action),
+                        None => action,
+                    }
+                },
+                Flow::Call(target) => match self.call_pointer(function, ins) {
+                    Some(pointer) => self.call(Target::Dynamic(pointer), &names.name(pointer), &layout.args[&addr], &names),
+                    None => self.call(target, &operand(1), &layout.args[&addr], &names),
+                },
+                Flow::Return if function.returns => format!("return {};", names.name(Param::Relative(1 - function.frame))),
+                Flow::Return => "return;".to_string(),
+                Flow::Halt | Flow::Next => self.statement(ins, &names, &layout.pointers),
+            };
+            let note = if self.written.contains(&addr) {" // rewritten at runtime"} else {""};
+            lines.push(format!("{}{}{}", indent(&open), statement, note));
+        }
+        while open.pop().is_some() {
+            lines.push(format!("{}}}", indent(&open)));
+        }
+        lines.push("}".to_string());
+    }
+    // `arb #-frame` just before the return
+    fn is_epilogue(&self, function: &Function, ins: &Instruction) -> bool {
+        ins.opcode == OpCode::AdjustBase && function.frame > 0 && ins.params[0] == Param::Immediate(-function.frame)
+            && function.code.get(&(ins.addr + ins.size())).is_some_and(|next| self.flow(next) == Flow::Return)
+    }
+    // A return address store, folded into the call that follows it
+    fn calls_next(&self, ins: &Instruction) -> bool {
+        disasm::decode(self.prog, ins.addr + ins.size()).is_some_and(|next| matches!(self.flow(&next), Flow::Call(_)))
+    }
+    // A call whose target word the function itself sets, from a single value, is a call through that value
+    // (day19's f225 calls its first argument)
+    fn call_pointer(&self, function: &Function, call: &Instruction) -> Option<Param> {
+        let word = call.addr + 2;
+        if !self.written.contains(&word) {
+            return None;
+        }
+        let pointer = function.code.values()
+            .filter(|ins| ins.params.last() == Some(&Param::Position(word as isize)))
+            .map(|ins| match (ins.opcode, ins.params.as_slice()) {
+                (OpCode::Add, [Param::Immediate(0), x, _]) | (OpCode::Add, [x, Param::Immediate(0), _]) => Some(*x),
+                (OpCode::Multiply, [Param::Immediate(1), x, _]) | (OpCode::Multiply, [x, Param::Immediate(1), _]) => Some(*x),
+                _ => None,
+            })
+            .collect::<Option<Vec<Param>>>();
+        match pointer.as_deref() {
+            Some([pointer]) => Some(*pointer),
+            _ => None,
+        }
+    }
+    // `pointer` is what a dynamic call goes through
+    fn call(&self, target: Target, pointer: &str, args: &BTreeMap<isize, String>, names: &Names) -> String {
+        let callee = target.addr().and_then(|addr| self.functions.get(&addr));
+        let params = match target {
+            Target::Dynamic(_) => args.keys().max().copied().unwrap_or(0),
+            _ => callee.map_or(0, |callee| callee.params as isize),
+        };
+        let args: Vec<String> = (1..=params)
+            .map(|slot| args.get(&slot).cloned().unwrap_or_else(|| names.name(Param::Relative(slot))))
+            .collect();
+        let result = if callee.is_some_and(|callee| callee.returns) {format!("{} = ", names.name(Param::Relative(1)))} else {String::new()};
+        match target {
+            Target::Fixed(addr) => format!("{}{}({});", result, function_name(addr), args.join(", ")),
+            Target::Via(via, addr) => format!("{}{}({}); // via g{}", result, function_name(addr), args.join(", "), via),
+            Target::Dynamic(_) => format!("(*{})({});", pointer, args.join(", ")),
+        }
+    }
+    fn statement(&self, ins: &Instruction, names: &Names, pointers: &BTreeMap<usize, String>) -> String {
+        let operand = |i: usize| self.operand(ins, i, names, pointers);
+        match ins.opcode {
+            OpCode::Write => format!("output({});", operand(0)),
+            OpCode::AdjustBase => match self.immediate(ins, 0) {
+                Some(n) if n < 0 => format!("rb -= {};", n.unsigned_abs()),
+                _ => format!("rb += {};", operand(0)),
+            },
+            OpCode::Halt => "halt;".to_string(),
+            OpCode::BranchNE | OpCode::BranchEQ => "// never jumps".to_string(),
+            _ => format!("{} = {};", operand(ins.params.len() - 1), self.expression(ins, names, pointers)),
+        }
+    }
+    // The value a storing instruction computes
+    fn expression(&self, ins: &Instruction, names: &Names, pointers: &BTreeMap<usize, String>) -> String {
+        let operand = |i: usize| self.operand(ins, i, names, pointers);
+        let (a, b) = (self.immediate(ins, 0), self.immediate(ins, 1));
+        match (ins.opcode, a, b) {
+            (OpCode::Read, _, _) => "input()".to_string(),
+            (OpCode::Add, Some(0), _) => operand(1),
+            (OpCode::Add, _, Some(0)) => operand(0),
+            (OpCode::Add, _, Some(n)) if n < 0 => format!("{} - {}", operand(0), n.unsigned_abs()),
+            (OpCode::Add, Some(n), _) if n < 0 => format!("{} - {}", operand(1), n.unsigned_abs()),
+            (OpCode::Add, _, _) => format!("{} + {}", operand(0), operand(1)),
+            (OpCode::Multiply, Some(1), _) => operand(1),
+            (OpCode::Multiply, _, Some(1)) => operand(0),
+            (OpCode::Multiply, Some(-1), _) => format!("-{}", operand(1)),
+            (OpCode::Multiply, _, Some(-1)) => format!("-{}", operand(0)),
+            (OpCode::Multiply, _, _) => format!("{} * {}", operand(0), operand(1)),
+            (OpCode::CompareLT, _, _) => format!("{} < {}", operand(0), operand(1)),
+            (OpCode::CompareEQ, _, _) => format!("{} == {}", operand(0), operand(1)),
+            _ => ins.to_string(),
+        }
+    }
+    // An immediate parameter that nothing rewrites
+    fn immediate(&self, ins: &Instruction, i: usize) -> Option<isize> {
+        match ins.params.get(i) {
+            Some(Param::Immediate(val)) if !self.written.contains(&(ins.addr + 1 + i)) => Some(*val),
+            _ => None,
+        }
+    }
+    // Parameter `i` as an expression.  Where the program rewrites the parameter word itself, the
+    // word holds the value (immediate) or the address (position) used.
+    fn operand(&self, ins: &Instruction, i: usize, names: &Names, pointers: &BTreeMap<usize, String>) -> String {
+        let word = ins.addr + 1 + i;
+        if !self.written.contains(&word) {
+            return names.name(ins.params[i]);
+        }
+        match ins.params[i] {
+            Param::Immediate(_) => format!("g{}", word),
+            Param::Position(_) => format!("mem[{}]", pointers.get(&word).cloned().unwrap_or_else(|| format!("g{}", word))),
+            Param::Relative(_) => format!("[rb+g{}]", word),
+        }
+    }
+}
+// What the frame slots of a function are called
+struct Names {
+    frame: isize,
+    params: usize,
+}
+impl Names {
+    fn of(function: &Function) -> Self {
+        Names { frame: function.frame, params: function.params }
+    }
+    fn name(&self, param: Param) -> String {
+        match param {
+            Param::Immediate(val) => val.to_string(),
+            Param::Position(addr) => format!("g{}", addr),
+            Param::Relative(offset) if -self.frame < offset && offset < 0 => {
+                let slot = (offset + self.frame) as usize;
+                if slot <= self.params {format!("arg{}", slot)} else {format!("local{}", slot - self.params)}
+            },
+            Param::Relative(offset) if offset > 0 => format!("out{}", offset), // the next call's arguments
+            Param::Relative(_) => param.to_string(),
+        }
+    }
+}
+fn function_name(entry: usize) -> String {
+    if entry == 0 {"main".to_string()} else {format!("f{}", entry)}
+}
+fn taken_when(cond: String, on_nonzero: bool) -> String {
+    if on_nonzero {cond} else {format!("!{}", cond)}
+}
+fn is_statement(ins: &Instruction) -> bool {
+    use OpCode::*;
+    matches!(ins.opcode, Add | Multiply | CompareLT | CompareEQ | Read | Write)
+}
+// Addresses stored to, leaving out stores whose address is itself rewritten (those are array writes)
+fn stores<'i>(code: impl Iterator<Item = &'i Instruction>, written: &BTreeSet<usize>) -> BTreeSet<usize> {
+    code.filter(|ins| ins.opcode.writes_last() && !written.contains(&(ins.addr + ins.size() - 1)))
+        .filter_map(|ins| match ins.params.last() {
+            Some(Param::Position(addr)) => usize::try_from(*addr).ok(),
+            _ => None,
+        })
+        .collect()
+}
+// Add or multiply of two immediates
+fn constant_store(ins: &Instruction) -> Option<isize> {
+    match (ins.opcode, ins.params.as_slice()) {
+        (OpCode::Add, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_add(*b),
+        (OpCode::Multiply, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_mul(*b),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_decompile() {
+    let prog = vec![
+        109,100, 203,1, 21101,11,0,0, 1106,0,14, 204,1, 99, // main: output f14(input)
+        109,3,                  // 14: fn f14(arg1), one local
+        21207,-2,0,-1,          // 16: local1 = arg1 < 0
+        1206,-1,30,             // 20: if (local1) {
+        21202,-2,-1,-2,         // 23:     arg1 = -arg1
+        1106,0,34,              // 27: } else {
+        21201,-2,1,-2,          // 30:     arg1 = arg1 + 1 }
+        21201,-2,-1,-2,         // 34: do { arg1 = arg1 - 1
+        22107,5,-2,-1,          // 38:     local1 = 5 < arg1
+        1205,-1,34,             // 42: } while (local1)
+        109,-3, 2106,0,0,       // 45: return arg1
+    ];
+    let functions = functions(&prog);
+    assert_eq!(functions.iter().map(|f| (f.name(), f.params, f.callers.clone())).collect::<Vec<_>>(),
+        vec![("main".to_string(), 0, vec![]), ("f14".to_string(), 1, vec![8])]);
+    let expected = "fn main() {
+    rb += 100;
+    out1 = input();
+    out1 = f14(out1);
+    output(out1);
+    halt;
+}
+
+// called from 8
+fn f14(arg1) {
+    var local1;
+    local1 = arg1 < 0;
+    if (local1) {
+        arg1 = -arg1;
+    } else {
+        arg1 = arg1 + 1;
+    }
+    do {
+        arg1 = arg1 - 1;
+        local1 = 5 < arg1;
+    } while (local1);
+    return arg1;
+}
+";
+    assert_eq!(decompile(&prog), expected);
+}
@@ -0,0 +1,166 @@
+//! Intcode disassembler.
+//! Parameters are written `[addr]` for position mode, `#val` for immediate mode and `[rb+n]`
+//! for relative mode.  Code and data share one address space, so a linear sweep can only guess
+//! which words are instructions; callers that know better (e.g. from a coverage run) say so.
+//! The `_with` variants also decode a set of extension opcodes (see extension.rs).
+
+use std::fmt;
+use crate::OpCode;
+use crate::extension::Extensions;
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Param {
+    Position(isize),
+    Immediate(isize),
+    Relative(isize),
+}
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Param::Position(addr) => write!(f, "[{}]", addr),
+            Param::Immediate(val) => write!(f, "#{}", val),
+            Param::Relative(offset) if *offset < 0 => write!(f, "[rb{}]", offset),
+            Param::Relative(offset) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Instruction {
+    pub addr: usize,
+    pub opcode: OpCode,
+    pub params: Vec<Param>,
+}
+impl Instruction {
+    /// Words occupied, opcode included
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+}
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+        if params.is_empty() {
+            write!(f, "{}", self.opcode.mnemonic())
+        } else {
+            write!(f, "{:<3} {}", self.opcode.mnemonic(), params.join(", "))
+        }
+    }
+}
+/// The instruction at `addr`, or None if the words there can't be one
+pub fn decode(prog: &[isize], addr: usize) -> Option<Instruction> {
+    decode_with(prog, addr, &Extensions::new())
+}
+pub fn decode_with(prog: &[isize], addr: usize, extensions: &Extensions) -> Option<Instruction> {
+    let word = *prog.get(addr)?;
+    if word < 0 {
+        return None;
+    }
+    let opcode = extensions.opcode(word % 100)?;
+    let mut modes = word / 100;
+    let mut params = Vec::new();
+    for i in 0..opcode.arity() {
+        let val = *prog.get(addr + 1 + i)?;
+        let param = match modes % 10 {
+            0 => Param::Position(val),
+            1 => Param::Immediate(val),
+            2 => Param::Relative(val),
+            _ => return None,
+        };
+        if let Param::Immediate(_) = param {
+            if opcode.writes_last() && i + 1 == opcode.arity() {
+                return None; // can't write to an immediate
+            }
+        }
+        params.push(param);
+        modes /= 10;
+    }
+    if modes != 0 {
+        return None;
+    }
+    Some(Instruction { addr, opcode, params })
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum Line {
+    Code(Instruction),
+    Data {addr: usize, val: isize},
+}
+impl Line {
+    pub fn addr(&self) -> usize {
+        match self {
+            Line::Code(instruction) => instruction.addr,
+            Line::Data {addr, ..} => *addr,
+        }
+    }
+    pub fn size(&self) -> usize {
+        match self {
+            Line::Code(instruction) => instruction.size(),
+            Line::Data {..} => 1,
+        }
+    }
+}
+/// What the caller knows about an address
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Hint {
+    Code,    // an instruction starts here
+    Data,    // never an instruction
+    Unknown, // decode it if it looks like one
+}
+/// Linear sweep of the whole image, guessing wherever there is no hint
+pub fn disassemble(prog: &[isize]) -> Vec<Line> {
+    disassemble_with(prog, |_| Hint::Unknown)
+}
+pub fn disassemble_with<H: Fn(usize) -> Hint>(prog: &[isize], hint: H) -> Vec<Line> {
+    disassemble_extended(prog, &Extensions::new(), hint)
+}
+pub fn disassemble_extended<H: Fn(usize) -> Hint>(prog: &[isize], extensions: &Extensions, hint: H) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < prog.len() {
+        let decoded = match hint(addr) {
+            Hint::Data => None,
+            Hint::Code | Hint::Unknown => decode_with(prog, addr, extensions),
+        };
+        // A guessed instruction must not swallow the start of a known one
+        let decoded = decoded.filter(|instruction| {
+            hint(addr) == Hint::Code || (addr + 1..addr + instruction.size()).all(|a| hint(a) != Hint::Code)
+        });
+        let line = match decoded {
+            Some(instruction) => Line::Code(instruction),
+            None => Line::Data {addr, val: prog[addr]},
+        };
+        addr += line.size();
+        lines.push(line);
+    }
+    lines
+}
+/// One listing line: address, raw words, then the decoded text
+pub fn format_line(prog: &[isize], line: &Line) -> String {
+    let words: Vec<String> = prog[line.addr()..line.addr() + line.size()].iter().map(|w| w.to_string()).collect();
+    let text = match line {
+        Line::Code(instruction) => instruction.to_string(),
+        Line::Data {val, ..} => {
+            let printable = (32..127).contains(val);
+            if printable {format!("data {:<8} '{}'", val, *val as u8 as char)} else {format!("data {}", val)}
+        },
+    };
+    format!("{:>6}: {:<24} {}", line.addr(), words.join(","), text)
+}
+
+#[test]
+fn test_decode() {
+    let prog = vec![1002,4,3,4,33,109,-2,21101,1,2,5,99];
+    assert_eq!(decode(&prog, 0).unwrap().to_string(), "mul [4], #3, [4]");
+    assert_eq!(decode(&prog, 5).unwrap().to_string(), "arb #-2");
+    assert_eq!(decode(&prog, 7).unwrap().to_string(), "add #1, #2, [rb+5]");
+    assert_eq!(decode(&prog, 11).unwrap().to_string(), "hlt");
+    assert_eq!(decode(&prog, 4), None); // opcode 33
+    assert_eq!(decode(&[11101,1,2,3], 0), None); // immediate destination
+}
+#[test]
+fn test_disassemble_with_hints() {
+    let prog = vec![1105,1,4,1,99];
+    // Address 3 looks like an add, but swallows the known Halt at 4
+    let lines = disassemble_with(&prog, |addr| if addr == 4 {Hint::Code} else {Hint::Unknown});
+    assert_eq!(lines.iter().map(|line| line.addr()).collect::<Vec<_>>(), vec![0,3,4]);
+    assert_eq!(lines[1], Line::Data {addr: 3, val: 1});
+}
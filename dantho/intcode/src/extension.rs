@@ -0,0 +1,212 @@
+//! Extra opcodes, for trying out additions to the instruction set (a debug print, a random number
+//! source, call and return) without forking the interpreter.
+//! An `Extension` is registered under an opcode number the machine doesn't already use, and says
+//! how many parameters it takes and whether it writes the last one; from there, parameter modes
+//! work as they do for the built-in opcodes.  The decoder, the disassembler, the interpreter and
+//! its trace all go by the registered set.  To only read a program that uses them, `Declared` gives
+//! an opcode's shape without its behaviour (`intcode disasm <program.txt> --ext 42=dbg/1`):
+//! ```text
+//!      0: 42,7                     dbg  [7]
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use crate::{Error, OpCode};
+
+/// An extension opcode, as the decoder sees it
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub struct Custom {
+    pub code: isize,
+    pub arity: usize,
+    pub writes_last: bool,
+    pub mnemonic: &'static str,
+}
+pub trait Extension: Send {
+    fn mnemonic(&self) -> &'static str;
+    /// Parameters, at most 3 (the modes of more don't fit in the opcode word)
+    fn arity(&self) -> usize;
+    /// Does the last parameter name the address being written?  It can't be immediate if so.
+    fn writes_last(&self) -> bool {
+        false
+    }
+    /// Run once.  `machine.args` holds each parameter's value, except that a written last
+    /// parameter starts as 0, and whatever is left there is stored.
+    fn execute(&mut self, machine: &mut Machine) -> Result<(), Error>;
+}
+/// What an extension can see and change
+pub struct Machine<'a> {
+    pub pc: usize, // the next instruction's address; set it to jump
+    pub relative_base: isize,
+    pub args: &'a mut [isize],
+}
+#[derive(Default)]
+pub struct Extensions {
+    by_code: BTreeMap<isize, Box<dyn Extension>>,
+}
+impl Extensions {
+    pub fn new() -> Self {
+        Extensions { by_code: BTreeMap::new() }
+    }
+    /// Claim `code`, a two-digit opcode the machine doesn't already have
+    pub fn register(&mut self, code: isize, extension: Box<dyn Extension>) -> Result<(), Error> {
+        let bad = |msg: &str| Err(Error::BadInput {msg: format!("Opcode {} ({}): {}", code, extension.mnemonic(), msg)});
+        if !(0..100).contains(&code) {
+            return bad("not an opcode, which is the last two digits of the instruction");
+        }
+        if OpCode::try_from(code).is_ok() || self.by_code.contains_key(&code) {
+            return bad("already taken");
+        }
+        if extension.arity() > 3 || (extension.writes_last() && extension.arity() == 0) {
+            return bad("parameters must number 0 to 3, with at least one to write to");
+        }
+        self.by_code.insert(code, extension);
+        Ok(())
+    }
+    /// The built-in or extension opcode numbered `code`
+    pub fn opcode(&self, code: isize) -> Option<OpCode> {
+        OpCode::try_from(code).ok().or_else(|| {
+            self.by_code.get(&code).map(|extension| OpCode::Extension(Custom {
+                code,
+                arity: extension.arity(),
+                writes_last: extension.writes_last(),
+                mnemonic: extension.mnemonic(),
+            }))
+        })
+    }
+    pub(crate) fn execute(&mut self, code: isize, machine: &mut Machine) -> Result<(), Error> {
+        match self.by_code.get_mut(&code) {
+            Some(extension) => extension.execute(machine),
+            None => Err(Error::IllegalOpcode { code }),
+        }
+    }
+}
+/// An opcode known only by its shape, so programs using it can be read: `<code>=<mnemonic>/<arity>`,
+/// with a `w` after the arity if it writes its last parameter (`42=dbg/1`, `22=sqr/2w`).
+/// Running one is an error.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Declared {
+    mnemonic: &'static str,
+    arity: usize,
+    writes_last: bool,
+}
+impl Declared {
+    pub fn parse(spec: &str) -> Result<(isize, Self), Error> {
+        let bad = || Error::BadInput {msg: format!("Expected <code>=<mnemonic>/<arity>[w], found '{}'", spec)};
+        let (code, rest) = spec.split_once('=').ok_or_else(bad)?;
+        let (mnemonic, arity) = rest.split_once('/').ok_or_else(bad)?;
+        let (arity, writes_last) = match arity.strip_suffix('w') {
+            Some(arity) => (arity, true),
+            None => (arity, false),
+        };
+        if mnemonic.is_empty() {
+            return Err(bad());
+        }
+        let code = code.parse().map_err(|_| bad())?;
+        let arity = arity.parse().map_err(|_| bad())?;
+        // Declared once per run, from the command line, so the name can live as long as the program
+        let mnemonic = Box::leak(mnemonic.to_string().into_boxed_str());
+        Ok((code, Declared { mnemonic, arity, writes_last }))
+    }
+}
+impl Extension for Declared {
+    fn mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+    fn arity(&self) -> usize {
+        self.arity
+    }
+    fn writes_last(&self) -> bool {
+        self.writes_last
+    }
+    fn execute(&mut self, _machine: &mut Machine) -> Result<(), Error> {
+        Err(Error::BadInput {msg: format!("{} is declared, but has no implementation", self.mnemonic)})
+    }
+}
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.by_code.iter().map(|(code, extension)| (code, extension.mnemonic()))).finish()
+    }
+}
+
+// call, ret and sqr, for the test.  call and ret share a return stack.
+#[cfg(test)]
+enum Example {
+    Call(std::sync::Arc<std::sync::Mutex<Vec<usize>>>),
+    Ret(std::sync::Arc<std::sync::Mutex<Vec<usize>>>),
+    Square,
+}
+#[cfg(test)]
+impl Extension for Example {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Example::Call(_) => "call",
+            Example::Ret(_) => "ret",
+            Example::Square => "sqr",
+        }
+    }
+    fn arity(&self) -> usize {
+        match self {
+            Example::Call(_) => 1,
+            Example::Ret(_) => 0,
+            Example::Square => 2,
+        }
+    }
+    fn writes_last(&self) -> bool {
+        matches!(self, Example::Square)
+    }
+    fn execute(&mut self, machine: &mut Machine) -> Result<(), Error> {
+        match self {
+            Example::Call(stack) => {
+                stack.lock().unwrap().push(machine.pc);
+                machine.pc = machine.args[0] as usize;
+            },
+            Example::Ret(stack) => {
+                machine.pc = stack.lock().unwrap().pop().ok_or(Error::BadInput {msg: "ret without call".to_string()})?;
+            },
+            Example::Square => machine.args[1] = machine.args[0] * machine.args[0],
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+fn examples() -> Extensions {
+    let stack = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut extensions = Extensions::new();
+    extensions.register(20, Box::new(Example::Call(stack.clone()))).unwrap();
+    extensions.register(21, Box::new(Example::Ret(stack))).unwrap();
+    extensions.register(22, Box::new(Example::Square)).unwrap();
+    extensions
+}
+#[test]
+fn test_extensions() {
+    use crate::{disasm, Intcode};
+    // Outputs the square of the square of its input, squaring in a subroutine at 9
+    let prog = vec![3,14, 120,9, 120,9, 4,14, 99, 22,14,14, 21, 99, 0];
+    let extensions = examples();
+    assert_eq!(disasm::decode_with(&prog, 2, &extensions).unwrap().to_string(), "call #9");
+    assert_eq!(disasm::decode_with(&prog, 9, &extensions).unwrap().to_string(), "sqr [14], [14]");
+    assert_eq!(disasm::decode_with(&prog, 12, &extensions).unwrap().to_string(), "ret");
+    assert_eq!(disasm::decode(&prog, 2), None);
+    let (mut computer, rx) = Intcode::with_inputs(prog.clone(), &[3]).unwrap();
+    computer.set_extensions(Some(extensions));
+    assert_eq!(computer.run_collecting(rx).unwrap(), vec![81]);
+
+    // The disassembler only needs to know their shape
+    let mut declared = Extensions::new();
+    for spec in &["20=call/1", "21=ret/0", "22=sqr/2w"] {
+        let (code, extension) = Declared::parse(spec).unwrap();
+        declared.register(code, Box::new(extension)).unwrap();
+    }
+    let listing = |extensions: &Extensions| -> Vec<String> {
+        disasm::disassemble_extended(&prog, extensions, |_| disasm::Hint::Unknown).iter()
+            .map(|line| disasm::format_line(&prog, line)).collect()
+    };
+    assert_eq!(listing(&declared), listing(&examples()));
+    assert!(Declared::parse("22=sqr").is_err());
+    assert!(Declared::parse("22=/2").is_err());
+
+    let mut extensions = examples();
+    assert!(extensions.register(5, Box::new(Example::Square)).is_err());
+    assert!(extensions.register(22, Box::new(Example::Square)).is_err());
+}
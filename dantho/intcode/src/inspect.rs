@@ -0,0 +1,86 @@
+//! Memory inspection: dump a range of memory as a table, and diff two snapshots to see what one
+//! input did -- a joystick move in day13, a `take` in day25.
+//! ```text
+//! intcode memdiff ../day25/input.txt --ascii --input west --then "take mouse" --range 4000..4700
+//!   4625: 3252 -> -1        # the mouse's location: -1 is "carried"
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use futures::prelude::*;
+use futures::executor::block_on;
+use futures::join;
+use crate::{Error, Intcode};
+
+const COLUMNS: usize = 10;
+
+/// Memory once the program has taken all of `inputs` and stopped, either halted or wanting more
+pub fn snapshot(prog: &[isize], inputs: &[isize]) -> Result<Vec<isize>, Error> {
+    let (mut computer, rx) = Intcode::with_inputs(prog.to_vec(), inputs)?;
+    let (result, _) = block_on(async { join!(computer.run_to_halt(), rx.collect::<Vec<_>>()) });
+    match result {
+        Ok(()) | Err(Error::ComputerComms {..}) => Ok(computer.into_memory()),
+        Err(e) => Err(e),
+    }
+}
+/// `range` of `memory`, ten words to a row, each row headed by its first address.
+/// Memory past the end reads as 0, as it does for the program.
+pub fn table(memory: &[isize], range: RangeInclusive<usize>) -> String {
+    let value = |addr: usize| *memory.get(addr).unwrap_or(&0);
+    let width = range.clone().map(|addr| value(addr).to_string().len()).max().unwrap_or(1);
+    let mut text = format!("{:>8}", "");
+    for column in 0..COLUMNS {
+        text += &format!(" {:>width$}", column, width = width);
+    }
+    text.push('\n');
+    let first_row = range.start() / COLUMNS * COLUMNS;
+    for row in (first_row..=*range.end()).step_by(COLUMNS) {
+        text += &format!("{:>7}:", row);
+        for addr in row..(row + COLUMNS).min(range.end() + 1) {
+            if addr < *range.start() {
+                text += &" ".repeat(width + 1);
+            } else {
+                text += &format!(" {:>width$}", value(addr), width = width);
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub struct Change {
+    pub addr: usize,
+    pub old: isize,
+    pub new: isize,
+}
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>6}: {} -> {}", self.addr, self.old, self.new)
+    }
+}
+/// Every address whose value differs between the snapshots, in order
+pub fn diff(before: &[isize], after: &[isize]) -> Vec<Change> {
+    let value = |memory: &[isize], addr: usize| *memory.get(addr).unwrap_or(&0);
+    (0..before.len().max(after.len()))
+        .map(|addr| Change { addr, old: value(before, addr), new: value(after, addr) })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+#[test]
+fn test_inspect() {
+    // Adds each input to a running total at [21], counting inputs at [22]
+    let prog = vec![3,20, 1,20,21,21, 1001,22,1,22, 1105,1,0];
+    let before = snapshot(&prog, &[5]).unwrap();
+    let after = snapshot(&prog, &[5, 7]).unwrap();
+    assert_eq!(diff(&before, &after), vec![
+        Change { addr: 20, old: 5, new: 7 },
+        Change { addr: 21, old: 5, new: 12 },
+        Change { addr: 22, old: 1, new: 2 },
+    ]);
+    assert_eq!(table(&after, 18..=22), [
+        "          0  1  2  3  4  5  6  7  8  9",
+        "     10:                          0  0",
+        "     20:  7 12  2",
+    ].iter().map(|line| format!("{}\n", line)).collect::<String>());
+}
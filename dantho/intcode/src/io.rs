@@ -0,0 +1,190 @@
+//! Where the computer's input comes from and where its output goes.
+//! The computer reads through an `Input` and writes through an `Output`.  Channels are one
+//! implementation (`Intcode::new` takes a Receiver and a Sender, as it always has); the rest
+//! here let a caller hand over a closure, an iterator, a shared Vec, or stdin and stdout:
+//! ```text
+//! let mut computer = Intcode::with_io(prog, InputIter([5].iter().cloned()), OutputFn(|val| println!("{}", val)));
+//! computer.run_blocking()?;
+//! ```
+//! Inputs answer Ready(None) once there will never be another value; a Read then fails, as it
+//! does when a channel's Sender is dropped, with the input's own `error()` if it has one (a bad line).
+
+use std::io::{self, BufRead, BufReader, Stdin, Stdout, Write};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use futures::prelude::*;
+use futures::channel::mpsc::{Receiver, Sender};
+use futures::task::{Context, Poll};
+use crate::Error;
+use crate::stream::{self, StreamFormat};
+
+pub trait Input: Send {
+    /// The next value, Ready(None) if there are no more, or Pending (having arranged to be woken)
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<isize>>;
+    /// Why there are no more values, if that's down to something worse than running out
+    fn error(&mut self) -> Option<Error> {
+        None
+    }
+}
+pub trait Output: Send {
+    /// Ready once a value can be sent.  Only a channel ever has to wait.
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+    fn send(&mut self, val: isize) -> Result<(), Error>;
+    /// The program halted: nothing more is coming
+    fn close(&mut self) {}
+}
+
+impl Input for Receiver<isize> {
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<isize>> {
+        self.poll_next_unpin(cx)
+    }
+}
+impl Output for Sender<isize> {
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Sender::poll_ready(self, cx).map_err(|_| dropped())
+    }
+    fn send(&mut self, val: isize) -> Result<(), Error> {
+        self.start_send(val).map_err(|_| dropped())
+    }
+    fn close(&mut self) {
+        self.close_channel();
+    }
+}
+fn dropped() -> Error {
+    Error::ComputerComms{msg:"Problem sending output data. Has receiver been dropped?".to_string()}
+}
+
+/// Input from a closure, called once per Read; None ends the input
+pub struct InputFn<F>(pub F);
+impl<F: FnMut() -> Option<isize> + Send> Input for InputFn<F> {
+    fn poll_input(&mut self, _cx: &mut Context<'_>) -> Poll<Option<isize>> {
+        Poll::Ready((self.0)())
+    }
+}
+/// Output to a closure, called with each value
+pub struct OutputFn<F>(pub F);
+impl<F: FnMut(isize) + Send> Output for OutputFn<F> {
+    fn send(&mut self, val: isize) -> Result<(), Error> {
+        (self.0)(val);
+        Ok(())
+    }
+}
+/// Input from an iterator, e.g. a fixed list of values
+pub struct InputIter<I>(pub I);
+impl<I: Iterator<Item = isize> + Send> Input for InputIter<I> {
+    fn poll_input(&mut self, _cx: &mut Context<'_>) -> Poll<Option<isize>> {
+        Poll::Ready(self.0.next())
+    }
+}
+/// Every output, kept in a Vec.  Clones share the Vec, so keep one to read it back.
+#[derive(Debug,Clone,Default)]
+pub struct Collector(Arc<Mutex<Vec<isize>>>);
+impl Collector {
+    pub fn new() -> Self {
+        Collector::default()
+    }
+    pub fn values(&self) -> Vec<isize> {
+        self.0.lock().unwrap().clone()
+    }
+    /// The values so far, leaving the Vec empty
+    pub fn take(&self) -> Vec<isize> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+impl Output for Collector {
+    fn send(&mut self, val: isize) -> Result<(), Error> {
+        self.0.lock().unwrap().push(val);
+        Ok(())
+    }
+}
+/// Input read a line at a time, as `intcode run` reads it.  Reading blocks the computer, which
+/// is fine with nothing else sharing its executor.
+pub struct LineInput<R> {
+    reader: R,
+    format: StreamFormat,
+    queued: VecDeque<isize>,
+    lines: usize,         // read so far
+    error: Option<Error>, // a line that couldn't be read or parsed
+}
+impl<R: BufRead + Send> LineInput<R> {
+    pub fn new(reader: R, format: StreamFormat) -> Self {
+        LineInput { reader, format, queued: VecDeque::new(), lines: 0, error: None }
+    }
+}
+impl LineInput<BufReader<Stdin>> {
+    pub fn stdin(format: StreamFormat) -> Self {
+        LineInput::new(BufReader::new(io::stdin()), format)
+    }
+}
+impl<R: BufRead + Send> Input for LineInput<R> {
+    fn poll_input(&mut self, _cx: &mut Context<'_>) -> Poll<Option<isize>> {
+        while self.queued.is_empty() && self.error.is_none() {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return Poll::Ready(None),
+                Err(e) => self.error = Some(Error::BadInput {msg: format!("Failure reading input: {}", e)}),
+                Ok(_) => {
+                    self.lines += 1;
+                    match stream::parse_line(line.trim_end_matches(['\r', '\n']), self.format) {
+                        Ok(vals) => self.queued.extend(vals),
+                        Err(Error::BadInput {msg}) => self.error = Some(Error::BadInput {msg: format!("Input line {}: {}", self.lines, msg)}),
+                        Err(e) => self.error = Some(e),
+                    }
+                },
+            }
+        }
+        Poll::Ready(self.queued.pop_front())
+    }
+    fn error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+}
+/// Output written a value at a time, as `intcode run` writes it
+pub struct LineOutput<W> {
+    writer: W,
+    format: StreamFormat,
+}
+impl<W: Write + Send> LineOutput<W> {
+    pub fn new(writer: W, format: StreamFormat) -> Self {
+        LineOutput { writer, format }
+    }
+}
+impl LineOutput<Stdout> {
+    pub fn stdout(format: StreamFormat) -> Self {
+        LineOutput::new(io::stdout(), format)
+    }
+}
+impl<W: Write + Send> Output for LineOutput<W> {
+    fn send(&mut self, val: isize) -> Result<(), Error> {
+        stream::write_value(&mut self.writer, val, self.format)
+    }
+}
+
+#[test]
+fn test_providers() {
+    use crate::Intcode;
+    // Outputs each input doubled, until an input of 0
+    let prog = vec![3,20, 1006,20,14, 1002,20,2,21, 4,21, 1105,1,0, 99];
+    let collector = Collector::new();
+    let mut computer = Intcode::with_io(prog.clone(), InputIter(vec![1, 2, 3, 0].into_iter()), collector.clone());
+    computer.run_blocking().unwrap();
+    assert_eq!(collector.values(), vec![2, 4, 6]);
+
+    let mut countdown = 3;
+    let lines = LineInput::new("5\n6,7\n".as_bytes(), StreamFormat::Numeric);
+    let mut computer = Intcode::with_io(prog.clone(), lines, OutputFn(move |val| {
+        countdown -= 1;
+        assert_eq!(val, [14, 12, 10][countdown]);
+    }));
+    assert!(computer.run_blocking().is_err()); // out of input, with no 0 to stop it
+    let lines = LineInput::new("5\n6,x\n".as_bytes(), StreamFormat::Numeric);
+    let mut computer = Intcode::with_io(prog.clone(), lines, OutputFn(|_| ()));
+    match computer.run_blocking() {
+        Err(Error::BadInput {msg}) => assert!(msg.starts_with("Input line 2:"), "{}", msg),
+        other => panic!("{:?}", other),
+    }
+    let mut computer = Intcode::with_io(prog, InputFn(|| Some(0)), OutputFn(|_| panic!("no output expected")));
+    computer.run_blocking().unwrap();
+}
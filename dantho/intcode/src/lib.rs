@@ -0,0 +1,497 @@
+//! https://adventofcode.com/2019/day/9
+//! Shared Intcode Computer -- day09's, made a crate the other days load and run their programs with
+//! (day17, day19, day21, day23 and day25 still step their own copies of intcode.rs)
+
+pub mod stream;
+pub mod server;
+pub mod record;
+pub mod search;
+pub mod disasm;
+pub mod coverage;
+pub mod program;
+pub mod patch;
+pub mod cheat;
+pub mod strings;
+pub mod decompile;
+pub mod optimize;
+pub mod threaded;
+pub mod extension;
+pub mod inspect;
+pub mod io;
+
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Debug;
+use std::pin::Pin;
+use futures::prelude::*;
+use futures::channel::mpsc::{channel,Sender,Receiver};
+use futures::executor::block_on;
+use futures::join;
+use futures::task::{Context, Poll};
+use record::InputRecorder;
+use coverage::Coverage;
+use cheat::Cheats;
+use extension::{Custom, Extensions};
+use io::{Input, Output};
+
+#[derive(Debug)]
+pub enum Error {
+    IllegalOpcode {code: isize},
+    IllegalMode {pc: usize, mode: isize},
+    IllegalAddress {pc: usize, addr: isize},
+    Overflow {pc: usize},
+    StepLimit {steps: usize}, // still running after this many instructions
+    ComputerComms {msg: String},
+    BadInput {msg: String},
+    Syntax {line: usize, column: usize, msg: String},
+}
+// Intcode Computer
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum OpCode {
+    Add,
+    Multiply,
+    Read,
+    Write,
+    BranchNE,
+    BranchEQ,
+    CompareLT,
+    CompareEQ,
+    AdjustBase,
+    Halt,
+    Extension(Custom), // registered with an Intcode, see extension.rs
+}
+impl TryFrom<isize> for OpCode {
+    type Error = Error;
+    fn try_from(code: isize) -> Result<Self, Self::Error> {
+        use OpCode::*;
+        let opcode = match code {
+            1 => Add,
+            2 => Multiply,
+            3 => Read,
+            4 => Write,
+            5 => BranchNE,
+            6 => BranchEQ,
+            7 => CompareLT,
+            8 => CompareEQ,
+            9 => AdjustBase,
+            99 => Halt,
+            _ => return Err(Error::IllegalOpcode { code }),
+        };
+        Ok(opcode)
+    }
+}
+impl OpCode {
+    /// Number of parameters following the opcode word
+    pub fn arity(&self) -> usize {
+        use OpCode::*;
+        match self {
+            Add | Multiply | CompareLT | CompareEQ => 3,
+            BranchNE | BranchEQ => 2,
+            Read | Write | AdjustBase => 1,
+            Halt => 0,
+            Extension(custom) => custom.arity,
+        }
+    }
+    /// Does the last parameter name the address being written?
+    pub fn writes_last(&self) -> bool {
+        use OpCode::*;
+        match self {
+            Add | Multiply | Read | CompareLT | CompareEQ => true,
+            Extension(custom) => custom.writes_last,
+            _ => false,
+        }
+    }
+    pub fn mnemonic(&self) -> &'static str {
+        use OpCode::*;
+        match self {
+            Add => "add",
+            Multiply => "mul",
+            Read => "in",
+            Write => "out",
+            BranchNE => "jnz",
+            BranchEQ => "jz",
+            CompareLT => "lt",
+            CompareEQ => "eq",
+            AdjustBase => "arb",
+            Halt => "hlt",
+            Extension(custom) => custom.mnemonic,
+        }
+    }
+}
+/// Where a computer has got to, to carry on from later: `Intcode::state` then `set_state`.
+/// A computer stopped by its input ending is left at that Read, so it carries on with the next input.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct State {
+    pub memory: Vec<isize>,
+    pub pc: usize,
+    pub relative_base: isize,
+    pub steps: usize,
+}
+pub struct Intcode {
+    prog: Vec<isize>,
+    input: Box<dyn Input>,
+    output: Box<dyn Output>,
+    pc: usize,
+    relative_base: isize,
+    idle_input: Option<isize>,
+    steps: usize,
+    recorder: Option<InputRecorder>,
+    coverage: Option<Coverage>,
+    cheats: Option<Cheats>,
+    extensions: Option<Extensions>,
+    trace: Option<Box<dyn std::io::Write + Send>>,
+}
+impl Intcode {
+    pub fn new(prog: Vec<isize>, input: Receiver<isize>, output: Sender<isize>) -> Self {
+        Intcode::with_io(prog, input, output)
+    }
+    /// A computer reading and writing through anything, not just channels: see io.rs
+    pub fn with_io<I: Input + 'static, O: Output + 'static>(prog: Vec<isize>, input: I, output: O) -> Self {
+        let (input, output): (Box<dyn Input>, Box<dyn Output>) = (Box::new(input), Box::new(output));
+        let pc = 0;
+        let relative_base = 0;
+        let idle_input = None;
+        let steps = 0;
+        let recorder = None;
+        let coverage = None;
+        let cheats = None;
+        let extensions = None;
+        let trace = None;
+        Intcode { prog, input, output, pc, relative_base, idle_input, steps, recorder, coverage, cheats, extensions, trace }
+    }
+    pub async fn run(&mut self) -> Result<(), Error> {
+        loop {
+            use OpCode::*;
+            self.steps += 1;
+            let mode = self.peek(self.pc) / 100;
+            let op = self.opcode(self.peek(self.pc) - mode * 100)?;
+            let m1 = mode - mode / 10 * 10;  let mode = mode / 10;
+            let m2 = mode - mode / 10 * 10;  let mode = mode / 10;
+            let m3 = mode - mode / 10 * 10;  let mode = mode / 10;
+            if mode != 0 {
+                return Err(Error::IllegalOpcode { code: self.peek(self.pc) });
+            }
+            if let Some(coverage) = &mut self.coverage {
+                coverage.mark_instruction(self.pc, op.arity());
+            }
+            if self.trace.is_some() {
+                self.trace_instruction()?;
+            }
+            match op {
+                Add => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    let p3 = self.dest(3, m3)?;
+                    self.poke(p3, v1.checked_add(v2).ok_or(Error::Overflow { pc: self.pc })?)?;
+                    self.pc += 4;
+                }
+                Multiply => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    let p3 = self.dest(3, m3)?;
+                    self.poke(p3, v1.checked_mul(v2).ok_or(Error::Overflow { pc: self.pc })?)?;
+                    self.pc += 4;
+                }
+                Read => {
+                    let p1 = self.dest(1, m1)?;
+                    let v1 = match self.idle_input {
+                        None => match future::poll_fn(|cx| self.input.poll_input(cx)).await {
+                            Some(v) => v,
+                            None => return Err(self.input_ended()),
+                        },
+                        Some(idle) => match future::poll_fn(|cx| self.input.poll_input(cx)).now_or_never() {
+                            Some(Some(v)) => v,
+                            Some(None) => return Err(self.input_ended()),
+                            None => {
+                                // Nothing queued. Let whoever shares our executor catch up before we poll again.
+                                YieldNow(false).await;
+                                idle
+                            },
+                        },
+                    };
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(self.steps, v1)?;
+                    }
+                    self.poke(p1, v1)?;
+                    // After the input is stored, so a value frozen where it lands stays frozen
+                    if let Some(cheats) = &self.cheats {
+                        cheats.sync(&mut self.prog, MAX_MEMORY);
+                    }
+                    self.pc += 2;
+                }
+                Write => {
+                    let v1 = self.param(1, m1)?;
+                    future::poll_fn(|cx| self.output.poll_ready(cx)).await?;
+                    self.output.send(v1)?;
+                    self.pc += 2;
+                }
+                BranchNE => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    if v1 != 0 {
+                        self.pc = v2 as usize
+                    } else {
+                        self.pc += 3
+                    };
+                }
+                BranchEQ => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    if v1 == 0 {
+                        self.pc = v2 as usize
+                    } else {
+                        self.pc += 3
+                    };
+                }
+                CompareLT => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    let p3 = self.dest(3, m3)?;
+                    self.poke(p3, if v1 < v2 {1} else {0})?;
+                    self.pc += 4;
+                }
+                CompareEQ => {
+                    let v1 = self.param(1, m1)?;
+                    let v2 = self.param(2, m2)?;
+                    let p3 = self.dest(3, m3)?;
+                    self.poke(p3, if v1 == v2 {1} else {0})?;
+                    self.pc += 4;
+                }
+                AdjustBase => {
+                    let v1 = self.param(1, m1)?;
+                    self.relative_base += v1;
+                    self.pc += 2;
+                }
+                Extension(custom) => {
+                    let modes = [m1, m2, m3];
+                    let mut args = vec![0; custom.arity];
+                    for (i, arg) in args.iter_mut().enumerate() {
+                        if !(custom.writes_last && i + 1 == custom.arity) {
+                            *arg = self.param(i + 1, modes[i])?;
+                        }
+                    }
+                    let dest = if custom.writes_last {Some(self.dest(custom.arity, modes[custom.arity - 1])?)} else {None};
+                    let mut machine = extension::Machine { pc: self.pc + 1 + custom.arity, relative_base: self.relative_base, args: &mut args };
+                    if let Some(extensions) = &mut self.extensions {
+                        extensions.execute(custom.code, &mut machine)?;
+                    }
+                    let (pc, relative_base) = (machine.pc, machine.relative_base);
+                    if let Some(dest) = dest {
+                        self.poke(dest, args[custom.arity - 1])?;
+                    }
+                    self.pc = pc;
+                    self.relative_base = relative_base;
+                }
+                Halt => break,
+            }
+        }
+        Ok(())
+    }
+    // A Read found no more input: why, if the input knows
+    fn input_ended(&mut self) -> Error {
+        self.input.error().unwrap_or_else(|| Error::ComputerComms{msg:"Expecting input, but stream has terminated.".to_string()})
+    }
+    /// Never block on Read: with nothing queued, the program reads `val` instead (day23's NIC convention is -1)
+    pub fn set_idle_input(&mut self, val: Option<isize>) {
+        self.idle_input = val;
+    }
+    /// Log every consumed input, with the instruction count at which it was read
+    pub fn set_recorder(&mut self, recorder: Option<InputRecorder>) {
+        self.recorder = recorder;
+    }
+    /// Track which addresses run as code and which are only read or written as data
+    pub fn set_coverage(&mut self, coverage: Option<Coverage>) {
+        self.coverage = coverage;
+    }
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+    /// Apply pokes and freezes, and snapshot memory, each time the program is handed an input
+    pub fn set_cheats(&mut self, cheats: Option<Cheats>) {
+        self.cheats = cheats;
+    }
+    /// Opcodes beyond the built-in ones
+    pub fn set_extensions(&mut self, extensions: Option<Extensions>) {
+        self.extensions = extensions;
+    }
+    /// Write each instruction to `trace` as it is about to run, with the step count and relative base
+    pub fn set_trace(&mut self, trace: Option<Box<dyn std::io::Write + Send>>) {
+        self.trace = trace;
+    }
+    /// Instructions executed so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+    pub fn memory(&self) -> &[isize] {
+        &self.prog
+    }
+    pub fn into_memory(self) -> Vec<isize> {
+        self.prog
+    }
+    pub fn state(&self) -> State {
+        State { memory: self.prog.clone(), pc: self.pc, relative_base: self.relative_base, steps: self.steps }
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.prog = state.memory;
+        self.pc = state.pc;
+        self.relative_base = state.relative_base;
+        self.steps = state.steps;
+    }
+    /// Run until Halt, then close the output channel so downstream readers see end-of-stream
+    pub async fn run_to_halt(&mut self) -> Result<(), Error> {
+        let result = self.run().await;
+        self.output.close();
+        result
+    }
+    /// `run_to_halt`, for callers with no executor of their own
+    pub fn run_blocking(&mut self) -> Result<(), Error> {
+        block_on(self.run_to_halt())
+    }
+    /// A computer whose inputs are all queued up front, and the Receiver for its outputs
+    pub fn with_inputs(prog: Vec<isize>, inputs: &[isize]) -> Result<(Self, Receiver<isize>), Error> {
+        const BUFFER_SIZE: usize = 100;
+        let (mut tx, computer_rx) = channel::<isize>(inputs.len());
+        let (computer_tx, rx) = channel::<isize>(BUFFER_SIZE);
+        for val in inputs {
+            tx.try_send(*val).map_err(|_| Error::ComputerComms {msg: "Failure queueing input.".to_string()})?;
+        }
+        // Dropping tx here makes running out of inputs an error, not a hang
+        Ok((Intcode::new(prog, computer_rx, computer_tx), rx))
+    }
+    /// Run until Halt, collecting every output sent to `rx`
+    pub fn run_collecting(&mut self, rx: Receiver<isize>) -> Result<Vec<isize>, Error> {
+        let (result, outputs) = block_on(async { join!(self.run_to_halt(), rx.collect::<Vec<_>>()) });
+        result?;
+        Ok(outputs)
+    }
+    fn opcode(&self, code: isize) -> Result<OpCode, Error> {
+        match &self.extensions {
+            Some(extensions) => extensions.opcode(code).ok_or(Error::IllegalOpcode { code }),
+            None => code.try_into(),
+        }
+    }
+    fn trace_instruction(&mut self) -> Result<(), Error> {
+        use std::io::Write;
+        let empty = Extensions::new();
+        let extensions = self.extensions.as_ref().unwrap_or(&empty);
+        let line = match disasm::decode_with(&self.prog, self.pc, extensions) {
+            Some(instruction) => disasm::Line::Code(instruction),
+            None => disasm::Line::Data {addr: self.pc, val: self.peek(self.pc)},
+        };
+        let text = format!("{:>10} rb={:<6} {}", self.steps, self.relative_base, disasm::format_line(&self.prog, &line));
+        if let Some(trace) = &mut self.trace {
+            writeln!(trace, "{}", text).map_err(|e| Error::ComputerComms {msg: format!("Failure writing trace: {}", e)})?;
+        }
+        Ok(())
+    }
+    // Memory grows on demand, so callers no longer pad programs out to PROG_MEM_SIZE
+    fn peek(&self, addr: usize) -> isize {
+        *self.prog.get(addr).unwrap_or(&0)
+    }
+    // A data read, as opposed to fetching the instruction itself
+    fn load(&mut self, addr: usize) -> isize {
+        if let Some(coverage) = &mut self.coverage {
+            if addr < MAX_MEMORY {
+                coverage.mark_read(addr);
+            }
+        }
+        self.peek(addr)
+    }
+    fn poke(&mut self, addr: usize, val: isize) -> Result<(), Error> {
+        if addr >= self.prog.len() {
+            if addr >= MAX_MEMORY {
+                return Err(Error::IllegalAddress { pc: self.pc, addr: addr as isize });
+            }
+            self.prog.resize(addr + 1, 0);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark_written(addr);
+        }
+        self.prog[addr] = val;
+        Ok(())
+    }
+    fn param(&mut self, offset: usize, mode: isize) -> Result<isize, Error> {
+        let p = self.peek(self.pc + offset);
+        match mode {
+            0 => Ok(self.load(self.address(p)?)),
+            1 => Ok(p),
+            2 => Ok(self.load(self.address(p + self.relative_base)?)),
+            _ => Err(Error::IllegalMode { pc: self.pc, mode }),
+        }
+    }
+    fn dest(&self, offset: usize, mode: isize) -> Result<usize, Error> {
+        let p = self.peek(self.pc + offset);
+        match mode {
+            0 => self.address(p),
+            2 => self.address(p + self.relative_base),
+            _ => Err(Error::IllegalMode { pc: self.pc, mode }),
+        }
+    }
+    fn address(&self, addr: isize) -> Result<usize, Error> {
+        if addr < 0 {
+            return Err(Error::IllegalAddress { pc: self.pc, addr });
+        }
+        Ok(addr as usize)
+    }
+}
+// Writes beyond this are treated as a runaway program rather than grown into
+const MAX_MEMORY: usize = 1 << 24;
+// Returns Pending exactly once, after asking to be woken right away
+struct YieldNow(bool);
+impl Future for YieldNow {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Run `prog` to Halt on a fixed list of inputs, returning its final memory and every output
+pub fn run_batch(prog: Vec<isize>, inputs: &[isize]) -> Result<(Vec<isize>, Vec<isize>), Error> {
+    let (mut computer, rx) = Intcode::with_inputs(prog, inputs)?;
+    let outputs = computer.run_collecting(rx)?;
+    Ok((computer.into_memory(), outputs))
+}
+
+#[cfg(test)]
+fn run_with_input(prog: &str, input: Vec<isize>) -> Vec<isize> {
+    let prog = program::parse(prog).unwrap();
+    run_batch(prog, &input).unwrap().1
+}
+#[test]
+fn test_quine() {
+    let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let expected: Vec<isize> = quine.split(',').map(|s| s.parse().unwrap()).collect();
+    assert_eq!(run_with_input(quine, vec![]), expected);
+}
+#[test]
+fn test_compare_eq_8() {
+    let prog = "3,9,8,9,10,9,4,9,99,-1,8";
+    assert_eq!(run_with_input(prog, vec![8]), vec![1]);
+    assert_eq!(run_with_input(prog, vec![7]), vec![0]);
+}
+#[test]
+fn test_state() {
+    // Add two inputs, stopping for want of the second and carrying on in another computer
+    let prog: Vec<isize> = vec![3,12,3,13,1,12,13,14,4,14,99,0,0,0,0];
+    let (mut computer, _rx) = Intcode::with_inputs(prog.clone(), &[5]).unwrap();
+    assert!(computer.run_blocking().is_err());
+    let state = computer.state();
+    assert_eq!((state.pc, state.memory[12]), (2, 5));
+    let (mut resumed, rx) = Intcode::with_inputs(prog, &[7]).unwrap();
+    resumed.set_state(state);
+    assert_eq!(resumed.run_collecting(rx).unwrap(), vec![12]);
+}
+#[test]
+fn test_runaway_write() {
+    // Store to a negative address is an error, not an abort
+    let prog = vec![1101,1,1,-5,99];
+    match run_batch(prog, &[]) {
+        Err(Error::IllegalAddress { pc: 0, addr: -5 }) => (),
+        other => panic!("Unexpected {:?}", other),
+    }
+}
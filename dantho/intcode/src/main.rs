@@ -0,0 +1,312 @@
+//! Intcode runner
+//!   intcode run <program.txt> [--ascii] [--replay <log>] [--record <log>] [--coverage <report>] [--trace <file>]
+//!     Inputs are read line-by-line from stdin and each output is written to stdout as it is produced.
+//!     A replayed input log is fed in ahead of stdin; --record logs every input consumed.
+//!     --coverage writes the disassembly, marked with what the run executed, read and wrote.
+//!     --trace writes each instruction as it runs, after the step count and relative base.
+//!   intcode serve <program.txt> [--tcp <addr> | --unix <path>] [--network <N>] [--ascii] [--replay <log>]
+//!     Same line protocol, one machine per socket connection (default --tcp 127.0.0.1:2019).
+//!     --network N waits for N connections, then runs them as a day23 network, routing its packets.
+//!   intcode trim <log> <out.log> (--values <N> | --steps <N>)
+//!     Keep the first N inputs of a log, or those consumed before instruction N.
+//!   intcode search <program.txt> (--mem <addr>=<lo>..<hi> | --in <index>=<lo>..<hi>)... --target <N>
+//!                  [--observe mem:<addr> | out:<n> | last] [--input <a,b,..>] [--linear] [--all]
+//!     Find values for the given addresses/inputs that make the observed value equal the target.
+//!     day02 part 2: intcode search input.txt --mem 1=0..99 --mem 2=0..99 --target 19690720
+//!   intcode disasm <program.txt> [--ext <code>=<mnemonic>/<arity>[w]]...
+//!     --ext declares an extension opcode, 'w' if it writes its last parameter: --ext 22=sqr/2w
+//!   intcode decompile <program.txt>
+//!     Pseudocode, with functions, arguments and locals, loops and if/else recovered from the compiler's idioms.
+//!   intcode optimize <program.txt> <out.txt> (--replay <log> | --input <a,b,..>)...
+//!     Peephole-optimize and minify, for runs like the profile given, then check the result on that profile.
+//!   intcode coverage <program.txt> <report> (--replay <log>)...
+//!     Coverage of several recorded sessions, merged into one report.
+//!   intcode pack <program.txt> <out.icb> [--gzip]
+//!     Convert to the compact binary image.  Every command reads text, images and gzip alike.
+//!   intcode strings <program.txt> [--input <line>]... [--steps <N>] [--min <N>]
+//!     Text in the image, plus text the program decodes as it prints, traced for N steps (default 1000000)
+//!     with the given lines fed in as ASCII input.
+//!   intcode mem <program.txt> [--range <lo>..<hi>] [--ascii] [--replay <log>] [--input <line>]...
+//!     Memory as a table, once the program has taken the given input and stopped for more (or halted).
+//!     --ascii reads each --input as a line of text rather than comma-separated numbers.
+//!   intcode memdiff <program.txt> --then <line> [--range <lo>..<hi>] [--ascii] [--replay <log>] [--input <line>]...
+//!     The addresses that one more input changes, with their values before and after.
+//!   intcode checksum <program.txt>
+//!     The checksum that keys sections of a patch file.
+//!   Commands that load a program also take --patch <file> (repeatable) to poke memory first.
+
+use std::env;
+use intcode::{Error, Intcode};
+use intcode::coverage::Coverage;
+use intcode::decompile;
+use intcode::disasm::{self, Hint};
+use intcode::extension::{Declared, Extensions};
+use intcode::inspect;
+use intcode::optimize;
+use intcode::patch;
+use intcode::program;
+use intcode::stream::{self, StreamFormat, StreamOptions};
+use intcode::server::{self, Endpoint, ServerConfig};
+use intcode::record::InputLog;
+use intcode::strings;
+use intcode::search::{Observable, Search, Strategy, Variable};
+
+const USAGE: &str = "Usage: intcode run <program.txt> [--ascii] [--replay <log>] [--record <log>] [--coverage <report>] [--trace <file>]
+       intcode serve <program.txt> [--tcp <addr> | --unix <path>] [--network <N>] [--ascii] [--replay <log>]
+       intcode trim <log> <out.log> (--values <N> | --steps <N>)
+       intcode search <program.txt> (--mem <addr>=<lo>..<hi> | --in <index>=<lo>..<hi>)... --target <N>
+                      [--observe mem:<addr> | out:<n> | last] [--input <a,b,..>] [--linear] [--all]
+       intcode disasm <program.txt> [--ext <code>=<mnemonic>/<arity>[w]]...
+       intcode decompile <program.txt>
+       intcode optimize <program.txt> <out.txt> (--replay <log> | --input <a,b,..>)...
+       intcode coverage <program.txt> <report> (--replay <log>)...
+       intcode pack <program.txt> <out.icb> [--gzip]
+       intcode strings <program.txt> [--input <line>]... [--steps <N>] [--min <N>]
+       intcode mem <program.txt> [--range <lo>..<hi>] [--ascii] [--replay <log>] [--input <line>]...
+       intcode memdiff <program.txt> --then <line> [--range <lo>..<hi>] [--ascii] [--replay <log>] [--input <line>]...
+       intcode checksum <program.txt>
+Commands taking a program also accept --patch <file>, repeatable.";
+
+fn main() -> Result<(),Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        ["run", filename, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let mut stream_options = stream_options(options)?;
+            stream_options.record_to = option_value(options, "--record").map(|log| log.to_string());
+            stream_options.coverage_to = option_value(options, "--coverage").map(|report| report.to_string());
+            stream_options.trace_to = option_value(options, "--trace").map(|file| file.to_string());
+            stream::run_stdio(prog, &stream_options)
+        },
+        ["serve", filename, options @ ..] => {
+            let endpoint = match (option_value(options, "--tcp"), option_value(options, "--unix")) {
+                (None, None) => Endpoint::Tcp("127.0.0.1:2019".to_string()),
+                (Some(addr), None) => Endpoint::Tcp(addr.to_string()),
+                #[cfg(unix)]
+                (None, Some(path)) => Endpoint::Unix(path.to_string()),
+                _ => return usage(),
+            };
+            let network_size = match option_value(options, "--network") {
+                Some(n) => Some(parse_count(n)?),
+                None => None,
+            };
+            let config = ServerConfig { endpoint, options: stream_options(options)?, network_size };
+            let prog = load_program(filename, options)?;
+            server::serve(prog, &config)
+        },
+        ["trim", log, out, options @ ..] => {
+            let log = InputLog::load(log)?;
+            let trimmed = match (option_value(options, "--values"), option_value(options, "--steps")) {
+                (Some(n), None) => log.first_values(parse_count(n)?),
+                (None, Some(n)) => log.before_step(parse_count(n)?),
+                _ => return usage(),
+            };
+            eprintln!("Kept {} of {} inputs", trimmed.entries.len(), log.entries.len());
+            trimmed.save(out)
+        },
+        ["search", filename, options @ ..] => {
+            let mut variables = Vec::new();
+            for spec in option_values(options, "--mem") {
+                let (addr, range) = parse_variable(spec)?;
+                variables.push(Variable::Memory { addr, range });
+            }
+            for spec in option_values(options, "--in") {
+                let (index, range) = parse_variable(spec)?;
+                variables.push(Variable::Input { index, range });
+            }
+            let target = match option_value(options, "--target") {
+                Some(n) => parse_value(n)?,
+                None => return usage(),
+            };
+            let observable = match option_value(options, "--observe").unwrap_or("mem:0").split(':').collect::<Vec<_>>().as_slice() {
+                ["mem", addr] => Observable::Memory(parse_count(addr)?),
+                ["out", n] => Observable::Output(parse_count(n)?),
+                ["last"] => Observable::LastOutput,
+                _ => return usage(),
+            };
+            let mut search = Search::new(load_program(filename, options)?, variables);
+            if let Some(input) = option_value(options, "--input") {
+                search.set_input(stream::parse_line(input, StreamFormat::Numeric)?);
+            }
+            match search.size() {
+                Some(size) => eprintln!("Searching {} combinations...", size),
+                None => eprintln!("Searching more combinations than can be counted..."),
+            }
+            if options.contains(&"--all") {
+                for answer in search.find_all(|outcome| outcome.observe(observable) == Some(target))? {
+                    println!("{:?}", answer);
+                }
+            } else {
+                let strategy = if options.contains(&"--linear") {Strategy::Linear} else {Strategy::BruteForce};
+                match search.solve_for(observable, target, strategy)? {
+                    Some(answer) => println!("{:?}", answer),
+                    None => println!("No answer."),
+                }
+            }
+            Ok(())
+        },
+        ["disasm", filename, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let mut extensions = Extensions::new();
+            for spec in option_values(options, "--ext") {
+                let (code, declared) = Declared::parse(spec)?;
+                extensions.register(code, Box::new(declared))?;
+            }
+            for line in disasm::disassemble_extended(&prog, &extensions, |_| Hint::Unknown) {
+                println!("{}", disasm::format_line(&prog, &line));
+            }
+            Ok(())
+        },
+        ["decompile", filename, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            print!("{}", decompile::decompile(&prog));
+            Ok(())
+        },
+        ["optimize", filename, out, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let mut profile = Vec::new();
+            for log in option_values(options, "--replay") {
+                profile.push(InputLog::load(log)?.values());
+            }
+            for input in option_values(options, "--input") {
+                profile.push(stream::parse_line(input, StreamFormat::Numeric)?);
+            }
+            if profile.is_empty() {
+                return usage();
+            }
+            let (optimized, report) = optimize::optimize(&prog, &profile)?;
+            eprintln!("{} operands and {} instructions folded, {} jumps threaded, {} dead words zeroed, {} trimmed",
+                report.folded_operands, report.folded_arithmetic, report.threaded_jumps, report.dead_words, report.trimmed_words);
+            eprintln!("{} words, was {}; {} steps over the profile, was {}",
+                optimized.len(), prog.len(), report.steps_after, report.steps_before);
+            if let Some(reason) = report.unproven {
+                eprintln!("Operands and dead words left alone: {}", reason);
+            }
+            program::save_text(out, &optimized)
+        },
+        ["coverage", filename, report, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let logs = option_values(options, "--replay");
+            if logs.is_empty() {
+                return usage();
+            }
+            let mut coverage = Coverage::new();
+            for log in logs {
+                let (mut computer, rx) = Intcode::with_inputs(prog.clone(), &InputLog::load(log)?.values())?;
+                computer.set_coverage(Some(Coverage::new()));
+                if let Err(e) = computer.run_collecting(rx) {
+                    eprintln!("{}: run stopped early: {:?}", log, e);
+                }
+                coverage.merge(&computer.take_coverage().unwrap_or_default());
+            }
+            coverage.save_report(report, &prog)
+        },
+        ["strings", filename, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let mut input = Vec::new();
+            for line in option_values(options, "--input") {
+                input.append(&mut stream::parse_line(line, StreamFormat::Ascii)?);
+            }
+            let max_steps = option_value(options, "--steps").map(parse_count).transpose()?.unwrap_or(1_000_000);
+            let min_len = option_value(options, "--min").map(parse_count).transpose()?.unwrap_or(4);
+            for region in strings::string_table(&prog, &input, max_steps, min_len) {
+                println!("{}", region.format());
+            }
+            Ok(())
+        },
+        ["mem", filename, options @ ..] => {
+            let memory = inspect::snapshot(&load_program(filename, options)?, &inputs(options)?)?;
+            let range = match option_value(options, "--range") {
+                Some(spec) => parse_range(spec)?,
+                None => 0..=memory.len().max(1) - 1,
+            };
+            print!("{}", inspect::table(&memory, range));
+            Ok(())
+        },
+        ["memdiff", filename, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            let mut inputs = inputs(options)?;
+            let before = inspect::snapshot(&prog, &inputs)?;
+            match option_value(options, "--then") {
+                Some(line) => inputs.append(&mut stream::parse_line(line, input_format(options))?),
+                None => return usage(),
+            }
+            let after = inspect::snapshot(&prog, &inputs)?;
+            let range = option_value(options, "--range").map(parse_range).transpose()?;
+            for change in inspect::diff(&before, &after) {
+                if range.as_ref().is_none_or(|range| range.contains(&change.addr)) {
+                    println!("{}", change);
+                }
+            }
+            Ok(())
+        },
+        ["checksum", filename] => {
+            println!("{:016x}", patch::checksum(&program::load(filename)?));
+            Ok(())
+        },
+        ["pack", filename, out, options @ ..] => {
+            let prog = load_program(filename, options)?;
+            program::save_image(out, &prog, options.contains(&"--gzip"))?;
+            eprintln!("Packed {} words", prog.len());
+            Ok(())
+        },
+        _ => usage(),
+    }
+}
+fn usage() -> Result<(),Error> {
+    eprintln!("{}", USAGE);
+    Err(Error::BadInput {msg: "bad command line".to_string()})
+}
+fn stream_options(options: &[&str]) -> Result<StreamOptions,Error> {
+    let format = if options.contains(&"--ascii") {StreamFormat::Ascii} else {StreamFormat::Numeric};
+    let mut stream_options = StreamOptions::new(format);
+    if let Some(log) = option_value(options, "--replay") {
+        stream_options.boot_input = InputLog::load(log)?.values();
+    }
+    Ok(stream_options)
+}
+// --replay, then each --input, as one list of input values
+fn inputs(options: &[&str]) -> Result<Vec<isize>,Error> {
+    let mut inputs = match option_value(options, "--replay") {
+        Some(log) => InputLog::load(log)?.values(),
+        None => Vec::new(),
+    };
+    for line in option_values(options, "--input") {
+        inputs.append(&mut stream::parse_line(line, input_format(options))?);
+    }
+    Ok(inputs)
+}
+fn input_format(options: &[&str]) -> StreamFormat {
+    if options.contains(&"--ascii") {StreamFormat::Ascii} else {StreamFormat::Numeric}
+}
+// "<lo>..<hi>", both ends inclusive
+fn parse_range(spec: &str) -> Result<std::ops::RangeInclusive<usize>,Error> {
+    let (lo, hi) = spec.split_once("..").ok_or_else(|| Error::BadInput {msg: format!("Expected <lo>..<hi>, found '{}'", spec)})?;
+    Ok(parse_count(lo)?..=parse_count(hi)?)
+}
+// "<position>=<lo>..<hi>", both ends inclusive
+fn parse_variable(spec: &str) -> Result<(usize, std::ops::RangeInclusive<isize>),Error> {
+    let bad_spec = || Error::BadInput {msg: format!("Expected <position>=<lo>..<hi>, found '{}'", spec)};
+    let (position, range) = spec.split_once('=').ok_or_else(bad_spec)?;
+    let (lo, hi) = range.split_once("..").ok_or_else(bad_spec)?;
+    Ok((parse_count(position)?, parse_value(lo)?..=parse_value(hi)?))
+}
+fn parse_value(n: &str) -> Result<isize,Error> {
+    n.parse::<isize>().map_err(|_| Error::BadInput {msg: format!("Not an integer: '{}'", n)})
+}
+fn parse_count(n: &str) -> Result<usize,Error> {
+    n.parse::<usize>().map_err(|_| Error::BadInput {msg: format!("Not a count: '{}'", n)})
+}
+// Every command takes --patch <file>, possibly more than once
+fn load_program(filename: &str, options: &[&str]) -> Result<Vec<isize>,Error> {
+    program::load_patched(filename, &option_values(options, "--patch"))
+}
+// The word following `name`, if `name` was given
+fn option_value<'a>(options: &[&'a str], name: &str) -> Option<&'a str> {
+    options.iter().position(|opt| *opt == name).and_then(|i| options.get(i + 1)).copied()
+}
+// The word following each occurrence of `name`
+fn option_values<'a>(options: &[&'a str], name: &str) -> Vec<&'a str> {
+    options.windows(2).filter(|pair| pair[0] == name).map(|pair| pair[1]).collect()
+}
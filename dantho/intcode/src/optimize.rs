@@ -0,0 +1,341 @@
+//! Peephole optimizer and minifier.
+//! Code and data share one address space and programs compute addresses at runtime, so nothing
+//! moves: every rewrite keeps the program's layout.
+//! - A position-mode read of a word nothing writes becomes an immediate, and arithmetic on two
+//!   immediates is done once, here, leaving `add #result, #0`.
+//! - A jump to an unconditional jump goes straight to the end of the chain, and a jump to an
+//!   instruction that changes nothing (`add [x], #0, [x]`, a branch never taken) goes past it.
+//!   An unconditional jump to a return or a halt becomes that return or halt.  That's where steps
+//!   are saved: folding only makes instructions simpler, and each still takes its step.
+//! - Words that are neither reachable code nor touched as data are zeroed, and trailing zeros
+//!   dropped -- memory past the end of the image reads as zero anyway.
+//!
+//! What is "touched" and "reachable" comes partly from a profile: runs on known inputs, with
+//! coverage on.  So the result is only promised to behave the same on programs' runs like those,
+//! and every profile run is repeated on the result to check that it does.  Returns through the
+//! stack go back to the addresses calls store there, and a jump through a word nothing writes goes
+//! where that word says.  But a program that indexes arrays by rewriting its own operands, or jumps
+//! through a pointer it changes, can touch words no profile run did; for those, operands and dead
+//! words are left alone and the report says why.
+//! ```text
+//! intcode optimize ../day25/input.txt day25.opt --replay day25.log
+//! ```
+
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use futures::prelude::*;
+use futures::executor::block_on;
+use futures::join;
+use crate::{Error, Intcode, OpCode};
+use crate::coverage::Coverage;
+use crate::disasm::{self, Instruction, Param};
+
+#[derive(Debug,Clone,Default,Eq,PartialEq)]
+pub struct Report {
+    pub folded_operands: usize,   // position reads turned immediate
+    pub folded_arithmetic: usize, // instructions computed ahead of time
+    pub threaded_jumps: usize,
+    pub dead_words: usize,        // non-zero words zeroed
+    pub trimmed_words: usize,     // zeros dropped from the end
+    pub unproven: Option<&'static str>, // why the program's data couldn't be pinned down, if it couldn't
+    pub steps_before: usize,      // over all profile runs
+    pub steps_after: usize,
+}
+// How a run ended, as far as anyone watching could tell
+#[derive(Debug,Clone,Eq,PartialEq)]
+struct Outcome {
+    outputs: Vec<isize>,
+    error: Option<String>,
+}
+/// An equivalent program for the runs in `profile` (each a list of inputs), checked by running it
+pub fn optimize(prog: &[isize], profile: &[Vec<isize>]) -> Result<(Vec<isize>, Report), Error> {
+    let mut coverage = Coverage::new();
+    let mut expected = Vec::new();
+    let mut report = Report::default();
+    for inputs in profile {
+        let (outcome, steps, run_coverage) = run(prog, inputs, true)?;
+        coverage.merge(&run_coverage.unwrap_or_default());
+        expected.push(outcome);
+        report.steps_before += steps;
+    }
+    let analysis = Analysis::new(prog, &coverage);
+    report.unproven = analysis.unproven;
+    let mut optimized = prog.to_vec();
+    for ins in &analysis.code {
+        if !analysis.fixed(ins) {
+            if analysis.unproven.is_none() {
+                report.folded_operands += analysis.fold_operands(&mut optimized, ins);
+            }
+            report.folded_arithmetic += analysis.fold_arithmetic(&mut optimized, ins.addr) as usize;
+        }
+    }
+    for ins in &analysis.code {
+        if !analysis.fixed(ins) {
+            report.threaded_jumps += analysis.thread_jump(&mut optimized, ins) as usize;
+        }
+    }
+    if analysis.unproven.is_none() {
+        for (addr, word) in optimized.iter_mut().enumerate() {
+            if *word != 0 && !analysis.live(addr) {
+                *word = 0;
+                report.dead_words += 1;
+            }
+        }
+    }
+    while optimized.last() == Some(&0) {
+        optimized.pop();
+        report.trimmed_words += 1;
+    }
+    for (i, (inputs, expected)) in profile.iter().zip(&expected).enumerate() {
+        let (outcome, steps, _) = run(&optimized, inputs, false)?;
+        if outcome != *expected {
+            return Err(Error::BadInput {msg: format!("Optimized program differs on profile run {}: {:?} instead of {:?}", i + 1, outcome, expected)});
+        }
+        report.steps_after += steps;
+    }
+    Ok((optimized, report))
+}
+fn run(prog: &[isize], inputs: &[isize], with_coverage: bool) -> Result<(Outcome, usize, Option<Coverage>), Error> {
+    let (mut computer, rx) = Intcode::with_inputs(prog.to_vec(), inputs)?;
+    if with_coverage {
+        computer.set_coverage(Some(Coverage::new()));
+    }
+    // Running out of input is how an interactive program's run ends, so keep what it printed first
+    let (result, outputs) = block_on(async { join!(computer.run_to_halt(), rx.collect::<Vec<_>>()) });
+    let outcome = Outcome { outputs, error: result.err().map(|e| format!("{:?}", e)) };
+    Ok((outcome, computer.steps(), computer.take_coverage()))
+}
+
+struct Analysis {
+    code: Vec<Instruction>,  // reachable instructions, as in the original image
+    words: BTreeSet<usize>,  // every word of those instructions
+    written: BTreeSet<usize>,
+    data: BTreeSet<usize>,   // read or written as data: never rewritten
+    unproven: Option<&'static str>, // why dead words and unwritten words can't be told apart
+}
+impl Analysis {
+    fn new(prog: &[isize], coverage: &Coverage) -> Self {
+        // Reachable: what ran, plus what static control flow leads to from there and from 0
+        let mut todo: Vec<usize> = (0..prog.len()).filter(|addr| coverage.executed(*addr)).collect();
+        todo.push(0);
+        let mut seen = BTreeSet::new();
+        let mut code = Vec::new();
+        let mut dynamic_jumps = false;
+        let mut pointers = Vec::new();
+        while let Some(addr) = todo.pop() {
+            if !seen.insert(addr) {
+                continue;
+            }
+            let ins = match disasm::decode(prog, addr) {
+                Some(ins) => ins,
+                None => continue,
+            };
+            let next = addr + ins.size();
+            match ins.opcode {
+                OpCode::Halt => (),
+                OpCode::BranchNE | OpCode::BranchEQ => {
+                    let taken = match ins.params[0] {
+                        Param::Immediate(val) => Some((val != 0) == (ins.opcode == OpCode::BranchNE)),
+                        _ => None,
+                    };
+                    if taken != Some(false) {
+                        match ins.params[1] {
+                            Param::Immediate(target) => todo.extend(usize::try_from(target).ok()),
+                            Param::Relative(_) => (), // a return, to one of the return addresses below
+                            // Through a pointer: known, unless something writes it (checked below)
+                            Param::Position(ptr) if usize::try_from(ptr).is_ok_and(|ptr| !coverage.written(ptr)) => {
+                                pointers.push(ptr as usize);
+                                todo.extend(usize::try_from(*prog.get(ptr as usize).unwrap_or(&0)).ok());
+                            },
+                            _ => dynamic_jumps = true,
+                        }
+                    }
+                    if taken != Some(true) {
+                        todo.push(next);
+                    }
+                },
+                _ => todo.push(next),
+            }
+            todo.extend(return_address(prog, &ins));
+            code.push(ins);
+        }
+        code.sort_by_key(|ins| ins.addr);
+        let words = code.iter().flat_map(|ins| ins.addr..ins.addr + ins.size()).collect();
+        let mut written: BTreeSet<usize> = (0..prog.len()).filter(|addr| coverage.written(*addr)).collect();
+        let mut data: BTreeSet<usize> = (0..prog.len()).filter(|addr| coverage.read(*addr)).collect();
+        for ins in &code {
+            for (i, param) in ins.params.iter().enumerate() {
+                if let Param::Position(addr) = param {
+                    if let Ok(addr) = usize::try_from(*addr) {
+                        data.insert(addr);
+                        if ins.opcode.writes_last() && i + 1 == ins.params.len() {
+                            written.insert(addr);
+                        }
+                    }
+                }
+            }
+        }
+        data.extend(&written);
+        dynamic_jumps |= pointers.iter().any(|ptr| written.contains(ptr));
+        // Rewriting an address in an instruction is how these programs index arrays, and then any word may be data
+        let computed = code.iter().any(|ins| {
+            let data_params = if matches!(ins.opcode, OpCode::BranchNE | OpCode::BranchEQ) {1} else {ins.params.len()};
+            (0..data_params).any(|i| matches!(ins.params[i], Param::Position(_)) && written.contains(&(ins.addr + 1 + i)))
+        });
+        let unproven = if computed {
+            Some("it computes data addresses")
+        } else if dynamic_jumps {
+            Some("it jumps through pointers")
+        } else {
+            None
+        };
+        Analysis { code, words, written, data, unproven }
+    }
+    // An instruction that rewrites itself, or is read as data, stays exactly as it is
+    fn fixed(&self, ins: &Instruction) -> bool {
+        (ins.addr..ins.addr + ins.size()).any(|addr| self.data.contains(&addr))
+    }
+    fn live(&self, addr: usize) -> bool {
+        self.words.contains(&addr) || self.data.contains(&addr)
+    }
+    fn fold_operands(&self, prog: &mut [isize], ins: &Instruction) -> usize {
+        let sources = ins.params.len() - ins.opcode.writes_last() as usize;
+        let mut folded = 0;
+        for (i, param) in ins.params[..sources].iter().enumerate() {
+            let addr = match param {
+                Param::Position(addr) => usize::try_from(*addr).ok().filter(|addr| !self.written.contains(addr)),
+                _ => None,
+            };
+            if let Some(addr) = addr {
+                prog[ins.addr + 1 + i] = *prog.get(addr).unwrap_or(&0);
+                prog[ins.addr] += 100 * 10_isize.pow(i as u32);
+                folded += 1;
+            }
+        }
+        folded
+    }
+    // Rewrites the instruction at `addr`, as it is now, into `add #result, #0, dest`
+    fn fold_arithmetic(&self, prog: &mut [isize], addr: usize) -> bool {
+        let ins = match disasm::decode(prog, addr) {
+            Some(ins) => ins,
+            None => return false,
+        };
+        let (a, b) = match ins.params.as_slice() {
+            [Param::Immediate(a), Param::Immediate(b), _] => (*a, *b),
+            _ => return false,
+        };
+        let result = match ins.opcode {
+            OpCode::Add if b == 0 => return false, // already folded
+            OpCode::Add => a.checked_add(b),
+            OpCode::Multiply => a.checked_mul(b),
+            OpCode::CompareLT => Some((a < b) as isize),
+            OpCode::CompareEQ => Some((a == b) as isize),
+            _ => None,
+        };
+        match result {
+            Some(result) => {
+                prog[addr] = prog[addr] / 10000 * 10000 + 1101;
+                prog[addr + 1] = result;
+                prog[addr + 2] = 0;
+                true
+            },
+            None => false,
+        }
+    }
+    fn thread_jump(&self, prog: &mut [isize], ins: &Instruction) -> bool {
+        let mut target = match (ins.opcode, ins.params.get(1)) {
+            (OpCode::BranchNE, Some(Param::Immediate(target))) | (OpCode::BranchEQ, Some(Param::Immediate(target))) => *target,
+            _ => return false,
+        };
+        let unconditional = |ins: &Instruction| match ins.params.first() {
+            Some(Param::Immediate(val)) => (*val != 0) == (ins.opcode == OpCode::BranchNE),
+            _ => false,
+        };
+        let mut seen = BTreeSet::new();
+        let end = loop {
+            let next = usize::try_from(target).ok()
+                .and_then(|addr| self.code.iter().find(|ins| ins.addr == addr))
+                .filter(|next| !self.fixed(next) && seen.insert(next.addr))
+                .and_then(|next| disasm::decode(prog, next.addr)); // as folded
+            match next {
+                Some(next) if matches!(next.opcode, OpCode::BranchNE | OpCode::BranchEQ) && unconditional(&next) => {
+                    match next.params[1] {
+                        Param::Immediate(next_target) => target = next_target,
+                        _ => break Some(next),
+                    }
+                },
+                Some(next) if no_op(&next) => target = (next.addr + next.size()) as isize,
+                Some(next) if next.opcode == OpCode::Halt => break Some(next),
+                _ => break None,
+            }
+        };
+        match end {
+            // Jumping to a return or a halt is the same as doing it here
+            Some(end) if unconditional(ins) => {
+                for i in 0..end.size() {
+                    prog[ins.addr + i] = prog[end.addr + i];
+                }
+                true
+            },
+            _ if target != prog[ins.addr + 2] => {
+                prog[ins.addr + 2] = target;
+                true
+            },
+            _ => false,
+        }
+    }
+}
+// A call pushes the address just past the jump into the callee: a constant stored on the stack,
+// then an unconditional jump, then that address
+fn return_address(prog: &[isize], ins: &Instruction) -> Option<usize> {
+    match (ins.params.last(), constant_store(ins)) {
+        (Some(Param::Relative(_)), Some(val)) => {
+            let jump = disasm::decode(prog, ins.addr + ins.size())?;
+            let after = jump.addr + jump.size();
+            let unconditional = matches!((jump.opcode, jump.params.as_slice()),
+                (OpCode::BranchNE, [Param::Immediate(c), _]) | (OpCode::BranchEQ, [Param::Immediate(c), _]) if (*c != 0) == (jump.opcode == OpCode::BranchNE));
+            Some(after).filter(|after| unconditional && usize::try_from(val) == Ok(*after))
+        },
+        _ => None,
+    }
+}
+// Changes nothing: adding 0 to a word or multiplying it by 1 in place, a branch never taken, `arb #0`
+fn no_op(ins: &Instruction) -> bool {
+    match (ins.opcode, ins.params.as_slice()) {
+        (OpCode::Add, [a, Param::Immediate(0), dest]) | (OpCode::Add, [Param::Immediate(0), a, dest]) |
+        (OpCode::Multiply, [a, Param::Immediate(1), dest]) | (OpCode::Multiply, [Param::Immediate(1), a, dest]) =>
+            a == dest && !matches!(a, Param::Immediate(_)),
+        (OpCode::BranchNE, [Param::Immediate(val), _]) => *val == 0,
+        (OpCode::BranchEQ, [Param::Immediate(val), _]) => *val != 0,
+        (OpCode::AdjustBase, [Param::Immediate(0)]) => true,
+        _ => false,
+    }
+}
+// Add or multiply of two immediates
+fn constant_store(ins: &Instruction) -> Option<isize> {
+    match (ins.opcode, ins.params.as_slice()) {
+        (OpCode::Add, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_add(*b),
+        (OpCode::Multiply, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_mul(*b),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_optimize() {
+    let prog = vec![1,12,13,14, 1105,1,15, 99,99,99,99,99, 5,7,0, 1106,0,20, 0,0, 4,14, 99];
+    let (optimized, report) = optimize(&prog, &[vec![]]).unwrap();
+    assert_eq!(optimized, vec![1101,12,0,14, 1105,1,20, 0,0,0,0,0, 5,7,0, 1106,0,20, 0,0, 4,14, 99]);
+    assert_eq!(report, Report {
+        folded_operands: 2, folded_arithmetic: 1, threaded_jumps: 1, dead_words: 5, trimmed_words: 0,
+        unproven: None, steps_before: 5, steps_after: 4,
+    });
+    // A call through the stack, to a loop whose branch lands on `add [30], #0, [30]` and a jump back
+    let prog = vec![109,40, 21101,9,0,1, 1105,1,20, 4,30, 99, 0,0,0,0,0,0,0,0,
+        1001,30,-1,30, 1005,30,31, 2105,1,1, 3, 1001,30,0,30, 1105,1,20];
+    let (optimized, report) = optimize(&prog, &[vec![]]).unwrap();
+    let mut expected = prog.clone();
+    expected[26] = 20;
+    assert_eq!(optimized, expected);
+    assert_eq!((report.threaded_jumps, report.unproven), (1, None));
+    assert_eq!((report.steps_before, report.steps_after), (16, 12));
+}
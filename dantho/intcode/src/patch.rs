@@ -0,0 +1,110 @@
+//! Memory patches -- the "hacks" puzzles ask for, kept as data next to the program.
+//! A patch file lists `address=value` pokes, separated by commas or whitespace, with '#' comments.
+//! A line `@ <checksum>` starts a section that only applies to the program with that checksum
+//! (see `checksum`, or `intcode checksum <program>`); pokes before any section apply to every program.
+//! ```text
+//! # day13: two quarters in the slot -- free play
+//! 0=2
+//! ```
+
+use std::fs;
+use crate::Error;
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub struct Poke {
+    pub addr: usize,
+    pub value: isize,
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+struct Section {
+    checksum: Option<u64>, // None: applies to every program
+    pokes: Vec<Poke>,
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Patch {
+    name: String,
+    sections: Vec<Section>,
+}
+impl Patch {
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let text = fs::read_to_string(filename).map_err(|e| bad(format!("Failure reading {}: {}", filename, e)))?;
+        Patch::parse(filename, &text)
+    }
+    /// `name` only labels error messages
+    pub fn parse(name: &str, text: &str) -> Result<Self, Error> {
+        let mut sections = vec![Section { checksum: None, pokes: Vec::new() }];
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let syntax = |msg: String| Error::Syntax {line: line_no + 1, column: 1, msg: format!("{}: {}", name, msg)};
+            if let Some(checksum) = line.strip_prefix('@') {
+                let checksum = u64::from_str_radix(checksum.trim(), 16)
+                    .map_err(|_| syntax(format!("Expected '@ <hex checksum>', found '{}'", line)))?;
+                sections.push(Section { checksum: Some(checksum), pokes: Vec::new() });
+                continue;
+            }
+            for poke in line.split(|c: char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty()) {
+                let parsed = poke.split_once('=').and_then(|(addr, value)| {
+                    Some(Poke { addr: addr.parse().ok()?, value: value.parse().ok()? })
+                });
+                let poke = parsed.ok_or_else(|| syntax(format!("Expected <address>=<value>, found '{}'", poke)))?;
+                sections.last_mut().unwrap().pokes.push(poke);
+            }
+        }
+        Ok(Patch { name: name.to_string(), sections })
+    }
+    /// The pokes that apply to `prog`: the unkeyed ones, and those in its section if it has one.
+    /// An error if the patch was written only for other programs (keyed sections, and no unkeyed pokes).
+    pub fn pokes_for(&self, prog: &[isize]) -> Result<Vec<Poke>, Error> {
+        let sum = checksum(prog);
+        let keyed = self.sections.iter().any(|section| section.checksum.is_some());
+        let matched = self.sections.iter().any(|section| section.checksum == Some(sum));
+        let unkeyed = self.sections.iter().any(|section| section.checksum.is_none() && !section.pokes.is_empty());
+        if keyed && !matched && !unkeyed {
+            return Err(bad(format!("{} has no section for this program (checksum {:016x})", self.name, sum)));
+        }
+        let pokes: Vec<Poke> = self.sections.iter()
+            .filter(|section| section.checksum.is_none() || section.checksum == Some(sum))
+            .flat_map(|section| section.pokes.iter().cloned())
+            .collect();
+        if let Some(poke) = pokes.iter().find(|poke| poke.addr >= prog.len()) {
+            return Err(bad(format!("{} pokes address {}, beyond the {}-word program", self.name, poke.addr, prog.len())));
+        }
+        Ok(pokes)
+    }
+    /// Apply to a freshly loaded program.  Returns the number of pokes.
+    pub fn apply(&self, prog: &mut [isize]) -> Result<usize, Error> {
+        let pokes = self.pokes_for(prog)?;
+        for poke in &pokes {
+            prog[poke.addr] = poke.value;
+        }
+        Ok(pokes.len())
+    }
+}
+/// 64-bit FNV-1a over the program words, to tell one puzzle input from another
+pub fn checksum(prog: &[isize]) -> u64 {
+    prog.iter()
+        .flat_map(|word| (*word as i64).to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3))
+}
+fn bad(msg: String) -> Error {
+    Error::BadInput {msg}
+}
+
+#[test]
+fn test_patch() {
+    let mut prog = vec![1,0,0,3,99];
+    let text = format!("# day02 style\n1=12, 2=2\n@ {:016x}\n4=98 # only this program\n@ 1234\n0=7\n", checksum(&prog));
+    let patch = Patch::parse("test.patch", &text).unwrap();
+    assert_eq!(patch.apply(&mut prog).unwrap(), 3);
+    assert_eq!(prog, vec![1,12,2,3,98]);
+    // prog has changed, so now only the unkeyed pokes apply
+    assert_eq!(patch.apply(&mut prog).unwrap(), 2);
+    // With nothing unkeyed, a patch for other programs refuses
+    let keyed_only = Patch::parse("test.patch", "@ 1234\n0=7\n@ 5678\n0=8\n").unwrap();
+    assert!(keyed_only.apply(&mut prog).is_err());
+    assert!(Patch::parse("test.patch", "5=1").unwrap().apply(&mut prog).is_err());
+    match Patch::parse("test.patch", "1=2\n3:4") {
+        Err(Error::Syntax {line: 2, ..}) => (),
+        other => panic!("{:?}", other),
+    }
+}
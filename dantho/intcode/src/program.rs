@@ -0,0 +1,201 @@
+//! Program loading.
+//! Text programs are integers separated by commas and/or whitespace -- one line, one value per
+//! line, a trailing comma, all fine -- with '#' starting a comment that runs to the end of the line.
+//! Programs can also be stored as a compact binary image (see `encode_image`), and either form
+//! may be gzip compressed.  The format is recognized from the first bytes of the file.
+//! ```text
+//! intcode pack ../day25/input.txt day25.icb --gzip     # 17K of text becomes 5K
+//! intcode run day25.icb --ascii
+//! ```
+
+use std::fs::File;
+use std::io::prelude::*;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use crate::Error;
+use crate::patch::Patch;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// Binary images start with these bytes, followed by the word count and then the words,
+/// all as LEB128 varints.  Words are zigzag encoded so that small negatives stay small.
+pub const IMAGE_MAGIC: &[u8] = b"ICI\x01";
+
+pub fn load(filename: &str) -> Result<Vec<isize>, Error> {
+    let mut bytes = Vec::new();
+    File::open(filename)
+        .and_then(|mut fd| fd.read_to_end(&mut bytes))
+        .map_err(|e| bad(format!("Failure reading {}: {}", filename, e)))?;
+    decode(&bytes).map_err(|e| match e {
+        Error::BadInput {msg} => bad(format!("{}: {}", filename, msg)),
+        Error::Syntax {line, column, msg} => Error::Syntax {line, column, msg: format!("{}: {}", filename, msg)},
+        e => e,
+    })
+}
+/// `load`, then apply each patch file.  Patches are all matched against the program as loaded.
+pub fn load_patched(filename: &str, patch_files: &[&str]) -> Result<Vec<isize>, Error> {
+    let original = load(filename)?;
+    let mut prog = original.clone();
+    for patch_file in patch_files {
+        for poke in Patch::load(patch_file)?.pokes_for(&original)? {
+            prog[poke.addr] = poke.value;
+        }
+    }
+    Ok(prog)
+}
+/// A program from the contents of a file in any of the supported formats
+pub fn decode(bytes: &[u8]) -> Result<Vec<isize>, Error> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let mut inflated = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut inflated).map_err(|e| bad(format!("Bad gzip data: {}", e)))?;
+        if inflated.starts_with(GZIP_MAGIC) {
+            return Err(bad("Compressed more than once".to_string()));
+        }
+        decode(&inflated)
+    } else if bytes.starts_with(IMAGE_MAGIC) {
+        decode_image(bytes)
+    } else {
+        let text = std::str::from_utf8(bytes).map_err(|e| bad(format!("Neither text nor a program image: {}", e)))?;
+        parse(text)
+    }
+}
+pub fn parse(text: &str) -> Result<Vec<isize>, Error> {
+    let mut prog = Vec::new();
+    let mut last_comma = None; // where the last comma was, if no value has followed it yet
+    let mut empty_field = true; // no value since the start or the last comma
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut token_start = None; // byte offset and column
+        // Columns count characters, not bytes.  A trailing space ends the last token on the line.
+        let chars = line.char_indices().chain(std::iter::once((line.len(), ' '))).enumerate();
+        for (column, (offset, ch)) in chars {
+            if ch == ',' || ch.is_whitespace() {
+                if let Some((start, start_column)) = token_start.take() {
+                    prog.push(parse_word(&line[start..offset], line_no, start_column)?);
+                    last_comma = None;
+                    empty_field = false;
+                }
+                if ch == ',' {
+                    if empty_field {
+                        return Err(match last_comma {
+                            Some((comma_line, comma_column)) => syntax(comma_line, comma_column, "Missing value between commas".to_string()),
+                            None => syntax(line_no, column, "Missing value before the first comma".to_string()),
+                        });
+                    }
+                    last_comma = Some((line_no, column));
+                    empty_field = true;
+                }
+            } else if token_start.is_none() {
+                token_start = Some((offset, column));
+            }
+        }
+    }
+    Ok(prog)
+}
+fn parse_word(word: &str, line_no: usize, column: usize) -> Result<isize, Error> {
+    word.parse::<isize>().map_err(|_| syntax(line_no, column, format!("Not an integer: '{}'", word)))
+}
+/// The compact binary form of `prog`, under half the size of the text
+pub fn encode_image(prog: &[isize]) -> Vec<u8> {
+    let mut bytes = IMAGE_MAGIC.to_vec();
+    push_varint(&mut bytes, prog.len() as u64);
+    for word in prog {
+        let word = *word as i64;
+        push_varint(&mut bytes, ((word << 1) ^ (word >> 63)) as u64);
+    }
+    bytes
+}
+pub fn decode_image(bytes: &[u8]) -> Result<Vec<isize>, Error> {
+    let mut rest = bytes.strip_prefix(IMAGE_MAGIC).ok_or_else(|| bad("Not a program image".to_string()))?;
+    let count = read_varint(&mut rest)?;
+    let mut prog = Vec::new();
+    for _ in 0..count {
+        let zigzag = read_varint(&mut rest)?;
+        let word = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+        prog.push(word as isize);
+    }
+    if !rest.is_empty() {
+        return Err(bad(format!("{} bytes of junk after the last word", rest.len())));
+    }
+    Ok(prog)
+}
+pub fn save_image(filename: &str, prog: &[isize], compress: bool) -> Result<(), Error> {
+    let image = encode_image(prog);
+    let bytes = if compress {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&image).and_then(|_| encoder.finish())
+            .map_err(|e| bad(format!("Failure compressing {}: {}", filename, e)))?
+    } else {
+        image
+    };
+    File::create(filename)
+        .and_then(|mut fd| fd.write_all(&bytes))
+        .map_err(|e| bad(format!("Failure writing {}: {}", filename, e)))
+}
+/// The usual text form: one line, comma separated
+pub fn save_text(filename: &str, prog: &[isize]) -> Result<(), Error> {
+    let words: Vec<String> = prog.iter().map(|word| word.to_string()).collect();
+    File::create(filename)
+        .and_then(|mut fd| writeln!(fd, "{}", words.join(",")))
+        .map_err(|e| bad(format!("Failure writing {}: {}", filename, e)))
+}
+fn push_varint(bytes: &mut Vec<u8>, mut val: u64) {
+    while val >= 0x80 {
+        bytes.push(val as u8 | 0x80);
+        val >>= 7;
+    }
+    bytes.push(val as u8);
+}
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, Error> {
+    let mut val = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes.split_first().ok_or_else(|| bad("Program image is truncated".to_string()))?;
+        *bytes = rest;
+        val |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(val);
+        }
+    }
+    Err(bad("Program image has a runaway varint".to_string()))
+}
+fn syntax(line_no: usize, column: usize, msg: String) -> Error {
+    Error::Syntax {line: line_no + 1, column: column + 1, msg}
+}
+fn bad(msg: String) -> Error {
+    Error::BadInput {msg}
+}
+
+#[test]
+fn test_parse() {
+    let text = "# day02, reformatted\n1,0,0,3,   # add\n  1 ,1,2,3\r\n\n99,\n";
+    assert_eq!(parse(text).unwrap(), vec![1,0,0,3,1,1,2,3,99]);
+    assert_eq!(parse("1 2\t-3\n").unwrap(), vec![1,2,-3]);
+    match parse("1,2,3\n4,x5,6") {
+        Err(Error::Syntax {line: 2, column: 3, ..}) => (),
+        other => panic!("{:?}", other),
+    }
+    match parse("1,2,\n,3") {
+        Err(Error::Syntax {line: 1, column: 4, ..}) => (),
+        other => panic!("{:?}", other),
+    }
+    // Columns are characters, whatever their size in bytes
+    match parse("1, 2 # ½\n9,\u{a0}é7") {
+        Err(Error::Syntax {line: 2, column: 4, ..}) => (),
+        other => panic!("{:?}", other),
+    }
+    match parse("  ,1,2") {
+        Err(Error::Syntax {line: 1, column: 3, ..}) => (),
+        other => panic!("{:?}", other),
+    }
+}
+#[test]
+fn test_image_round_trip() {
+    let prog = vec![109,-1,0,63,64,-64,8191,-8192,isize::MAX,isize::MIN,99];
+    let image = encode_image(&prog);
+    assert_eq!(&image[..6], &[b'I',b'C',b'I',1,11,218]);
+    assert_eq!(decode(&image).unwrap(), prog);
+    let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+    gzipped.write_all(&image).unwrap();
+    assert_eq!(decode(&gzipped.finish().unwrap()).unwrap(), prog);
+    assert!(decode_image(&image[..image.len() - 1]).is_err());
+}
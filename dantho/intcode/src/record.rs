@@ -0,0 +1,110 @@
+//! Record-and-replay of Intcode input streams.
+//! A log is plain text, one consumed input per line: the instruction count at which the
+//! computer read it, then the value.  Lines starting with '#' are comments.
+//! ```text
+//! intcode run ../day25/input.txt --ascii --record day25.log      # explore by hand
+//! intcode trim day25.log short.log --values 120                  # keep the first 120 inputs
+//! intcode run ../day25/input.txt --ascii --replay short.log      # back where we were, keep playing
+//! ```
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use crate::Error;
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub struct LogEntry {
+    pub step: usize, // instruction count at this Read; the program's first instruction is step 1
+    pub value: isize,
+}
+#[derive(Debug,Clone,Default,Eq,PartialEq)]
+pub struct InputLog {
+    pub entries: Vec<LogEntry>,
+}
+impl InputLog {
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let fd = File::open(filename).map_err(|e| bad(format!("Failure opening {}: {}", filename, e)))?;
+        let mut entries = Vec::new();
+        for (line_no, line) in BufReader::new(fd).lines().enumerate() {
+            let line = line.map_err(|e| bad(format!("Failure reading {}: {}", filename, e)))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            entries.push(parse_entry(line).ok_or_else(|| bad(format!("{}:{}: expected '<step> <value>', found '{}'", filename, line_no + 1, line)))?);
+        }
+        Ok(InputLog { entries })
+    }
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        let fd = File::create(filename).map_err(|e| bad(format!("Failure creating {}: {}", filename, e)))?;
+        let mut out = BufWriter::new(fd);
+        let mut written = writeln!(out, "{}", LOG_HEADER);
+        for entry in &self.entries {
+            written = written.and_then(|_| write_entry(&mut out, entry));
+        }
+        written.and_then(|_| out.flush()).map_err(|e| bad(format!("Failure writing {}: {}", filename, e)))
+    }
+    /// Just the values, in the order they were consumed -- what a replay feeds back in
+    pub fn values(&self) -> Vec<isize> {
+        self.entries.iter().map(|entry| entry.value).collect()
+    }
+    /// The first `count` inputs
+    pub fn first_values(&self, count: usize) -> Self {
+        InputLog { entries: self.entries.iter().take(count).cloned().collect() }
+    }
+    /// Every input consumed before instruction number `step`
+    pub fn before_step(&self, step: usize) -> Self {
+        InputLog { entries: self.entries.iter().filter(|entry| entry.step < step).cloned().collect() }
+    }
+}
+/// Appends each input to a log file as the computer consumes it, so a crash or ^C loses nothing
+pub struct InputRecorder {
+    out: Box<dyn Write + Send>,
+}
+impl InputRecorder {
+    pub fn create(filename: &str) -> Result<Self, Error> {
+        let fd = File::create(filename).map_err(|e| bad(format!("Failure creating {}: {}", filename, e)))?;
+        let mut out: Box<dyn Write + Send> = Box::new(fd);
+        writeln!(out, "{}", LOG_HEADER).map_err(|e| bad(format!("Failure writing {}: {}", filename, e)))?;
+        Ok(InputRecorder { out })
+    }
+    pub fn record(&mut self, step: usize, value: isize) -> Result<(), Error> {
+        write_entry(&mut self.out, &LogEntry { step, value })
+            .and_then(|_| self.out.flush())
+            .map_err(|e| Error::ComputerComms {msg: format!("Failure writing input log: {}", e)})
+    }
+}
+const LOG_HEADER: &str = "# intcode input log: <step> <value>";
+fn write_entry<W: Write>(out: &mut W, entry: &LogEntry) -> std::io::Result<()> {
+    writeln!(out, "{} {}", entry.step, entry.value)
+}
+fn parse_entry(line: &str) -> Option<LogEntry> {
+    let mut words = line.split_whitespace();
+    let step = words.next()?.parse().ok()?;
+    let value = words.next()?.parse().ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Some(LogEntry { step, value })
+}
+fn bad(msg: String) -> Error {
+    Error::BadInput {msg}
+}
+
+#[test]
+fn test_parse_entry() {
+    assert_eq!(parse_entry("1234 -1"), Some(LogEntry { step: 1234, value: -1 }));
+    assert_eq!(parse_entry("1234"), None);
+    assert_eq!(parse_entry("1234 5 6"), None);
+}
+#[test]
+fn test_trim() {
+    let log = InputLog { entries: vec![
+        LogEntry { step: 10, value: 1 },
+        LogEntry { step: 20, value: 2 },
+        LogEntry { step: 30, value: 3 },
+    ]};
+    assert_eq!(log.first_values(2).values(), vec![1,2]);
+    assert_eq!(log.before_step(30).values(), vec![1,2]);
+    assert_eq!(log.before_step(5).values(), vec![]);
+}
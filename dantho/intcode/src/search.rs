@@ -0,0 +1,330 @@
+//! Input search -- day02's noun/verb hunt, generalized.
+//! Pick some memory addresses and/or input positions, give each a range, and search for
+//! assignments whose run satisfies a predicate.  Brute force is spread over every core.
+//! When the observed value turns out to be linear in every variable (day02's memory[0] is
+//! 100*k*noun + verb + c), `solve_for` can skip the brute force and solve for one variable directly.
+//! A run still going after `max_steps` instructions (an assignment that sends the program into a
+//! loop) counts as no match.
+
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use crate::Error;
+use crate::threaded::Threaded;
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum Variable {
+    Memory {addr: usize, range: RangeInclusive<isize>}, // poked before the run starts
+    Input {index: usize, range: RangeInclusive<isize>}, // the index'th value read
+}
+impl Variable {
+    fn range(&self) -> &RangeInclusive<isize> {
+        match self {
+            Variable::Memory {range, ..} | Variable::Input {range, ..} => range,
+        }
+    }
+    /// None if there are more values than a usize holds
+    fn size(&self) -> Option<usize> {
+        let range = self.range();
+        if range.is_empty() {Some(0)} else {usize::try_from(*range.end() as i128 - *range.start() as i128 + 1).ok()}
+    }
+}
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Observable {
+    Memory(usize), // e.g. Memory(0), day02's answer
+    Output(usize), // the n'th output
+    LastOutput,
+}
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Strategy {
+    BruteForce,
+    Linear, // probe for linearity first; falls back to BruteForce if the probes disagree
+}
+#[derive(Debug,Clone)]
+pub struct Outcome {
+    pub memory: Vec<isize>,
+    pub outputs: Vec<isize>,
+}
+impl Outcome {
+    pub fn observe(&self, observable: Observable) -> Option<isize> {
+        match observable {
+            Observable::Memory(addr) => self.memory.get(addr).cloned(),
+            Observable::Output(n) => self.outputs.get(n).cloned(),
+            Observable::LastOutput => self.outputs.last().cloned(),
+        }
+    }
+}
+pub struct Search {
+    prog: Threaded, // translated once, for all the runs
+    variables: Vec<Variable>,
+    input: Vec<isize>,
+    threads: usize,
+}
+impl Search {
+    pub const MAX_STEPS: usize = 10_000_000;
+    pub fn new(prog: Vec<isize>, variables: Vec<Variable>) -> Self {
+        let input = Vec::new();
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let mut prog = Threaded::new(&prog);
+        prog.set_step_limit(Some(Search::MAX_STEPS));
+        Search { prog, variables, input, threads }
+    }
+    /// Instructions a run may take before it's given up on (default `MAX_STEPS`)
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.prog.set_step_limit(Some(max_steps));
+    }
+    /// Fixed inputs for every run. Input variables overwrite their positions in this list.
+    pub fn set_input(&mut self, input: Vec<isize>) {
+        self.input = input;
+    }
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+    /// Number of assignments a brute force would try, or None if that's more than a usize holds
+    pub fn size(&self) -> Option<usize> {
+        self.variables.iter().try_fold(1usize, |size, var| size.checked_mul(var.size()?))
+    }
+    /// One run with `assignment` (one value per variable, in order)
+    pub fn evaluate(&self, assignment: &[isize]) -> Result<Outcome, Error> {
+        let mut pokes = Vec::new();
+        let mut input = self.input.clone();
+        for (var, val) in self.variables.iter().zip(assignment) {
+            match var {
+                Variable::Memory {addr, ..} => pokes.push((*addr, *val)),
+                Variable::Input {index, ..} => {
+                    if *index >= input.len() {
+                        input.resize(index + 1, 0);
+                    }
+                    input[*index] = *val;
+                },
+            }
+        }
+        let (memory, outputs) = self.prog.run_patched(&pokes, &input)?;
+        Ok(Outcome { memory, outputs })
+    }
+    /// First satisfying assignment, counting the first variable as most significant.
+    /// Runs that fail (bad opcode, out of input, too many steps, ...) simply don't match.
+    pub fn find_first<P>(&self, predicate: P) -> Result<Option<Vec<isize>>, Error>
+        where P: Fn(&Outcome) -> bool + Sync
+    {
+        Ok(self.brute_force(&predicate, true)?.into_iter().next())
+    }
+    /// Every satisfying assignment, in order
+    pub fn find_all<P>(&self, predicate: P) -> Result<Vec<Vec<isize>>, Error>
+        where P: Fn(&Outcome) -> bool + Sync
+    {
+        self.brute_force(&predicate, false)
+    }
+    /// First assignment for which `observable` equals `target`
+    pub fn solve_for(&self, observable: Observable, target: isize, strategy: Strategy) -> Result<Option<Vec<isize>>, Error> {
+        if strategy == Strategy::Linear {
+            if let Some(model) = self.linear_model(observable)? {
+                return self.solve_linear(&model, observable, target);
+            }
+        }
+        self.find_first(|outcome| outcome.observe(observable) == Some(target))
+    }
+    fn brute_force<P>(&self, predicate: &P, first_only: bool) -> Result<Vec<Vec<isize>>, Error>
+        where P: Fn(&Outcome) -> bool + Sync
+    {
+        let total = self.size().ok_or_else(too_many)?;
+        let best = AtomicUsize::new(usize::MAX); // lowest hit so far, when first_only
+        let found = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for worker in 0..self.threads {
+                let (best, found) = (&best, &found);
+                scope.spawn(move || {
+                    for index in (worker..total).step_by(self.threads) {
+                        if first_only && index > best.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let assignment = self.assignment(index);
+                        let hit = match self.evaluate(&assignment) {
+                            Ok(outcome) => predicate(&outcome),
+                            Err(_) => false,
+                        };
+                        if hit {
+                            found.lock().unwrap().push((index, assignment));
+                            if first_only {
+                                best.fetch_min(index, Ordering::Relaxed);
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        let mut found = found.into_inner().unwrap();
+        found.sort();
+        Ok(found.into_iter().map(|(_, assignment)| assignment).collect())
+    }
+    // Mixed-radix decode of a brute force index; the last variable varies fastest.  Only for a
+    // search whose size() is known, so every variable's is.
+    fn assignment(&self, mut index: usize) -> Vec<isize> {
+        let mut assignment = vec![0; self.variables.len()];
+        for (i, var) in self.variables.iter().enumerate().rev() {
+            let size = var.size().unwrap_or(usize::MAX);
+            assignment[i] = var.range().start().wrapping_add((index % size) as isize);
+            index /= size;
+        }
+        assignment
+    }
+    // observable = constant + sum(coefficient[i] * (value[i] - start[i])), if every probe agrees
+    fn linear_model(&self, observable: Observable) -> Result<Option<LinearModel>, Error> {
+        if self.size() == Some(0) {
+            return Ok(None);
+        }
+        let low: Vec<isize> = self.variables.iter().map(|var| *var.range().start()).collect();
+        let high: Vec<isize> = self.variables.iter().map(|var| *var.range().end()).collect();
+        let probe = |assignment: &[isize]| -> Option<isize> {
+            self.evaluate(assignment).ok().and_then(|outcome| outcome.observe(observable))
+        };
+        let constant = match probe(&low) {
+            Some(val) => val,
+            None => return Ok(None),
+        };
+        let mut coefficients = Vec::new();
+        for i in 0..low.len() {
+            if low[i] == high[i] {
+                coefficients.push(0);
+                continue;
+            }
+            let mut next = low.clone();
+            next[i] += 1;
+            match probe(&next).and_then(|val| val.checked_sub(constant)) {
+                Some(coefficient) => coefficients.push(coefficient),
+                None => return Ok(None),
+            }
+        }
+        let model = LinearModel { low: low.clone(), constant, coefficients };
+        // Check the far corner, each variable at its top end, and the middle
+        let middle = low.iter().zip(&high).map(|(l, h)| ((*l as i128 + *h as i128) / 2) as isize).collect();
+        let mut checks = vec![high.clone(), middle];
+        for i in 0..low.len() {
+            let mut check = low.clone();
+            check[i] = high[i];
+            checks.push(check);
+        }
+        for check in checks {
+            match (probe(&check), model.predict(&check)) {
+                (Some(val), Some(predicted)) if val as i128 == predicted => (),
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(model))
+    }
+    // Enumerate every variable but one; the pivot falls out of the equation
+    fn solve_linear(&self, model: &LinearModel, observable: Observable, target: isize) -> Result<Option<Vec<isize>>, Error> {
+        let pivot = match (0..self.variables.len())
+            .filter(|i| model.coefficients[*i] != 0)
+            .max_by_key(|i| self.variables[*i].size().unwrap_or(usize::MAX)) {
+            Some(pivot) => pivot,
+            None => {
+                // Constant: either everything matches or nothing does
+                return Ok(if model.constant == target {Some(model.low.clone())} else {None});
+            },
+        };
+        let others: Vec<Variable> = self.variables.iter().enumerate()
+            .filter(|(i, _)| *i != pivot)
+            .map(|(_, var)| var.clone())
+            .collect();
+        let rest = Search { prog: Threaded::new(&[]), variables: others, input: Vec::new(), threads: 1 };
+        let mut solutions = Vec::new();
+        for index in 0..rest.size().ok_or_else(too_many)? {
+            let mut assignment = rest.assignment(index);
+            assignment.insert(pivot, model.low[pivot]);
+            // Too big to predict even in i128: leave it to a brute force
+            let remainder = match model.predict(&assignment).and_then(|predicted| (target as i128).checked_sub(predicted)) {
+                Some(remainder) => remainder,
+                None => return self.find_first(|outcome| outcome.observe(observable) == Some(target)),
+            };
+            let coefficient = model.coefficients[pivot] as i128;
+            if remainder % coefficient != 0 {
+                continue;
+            }
+            let pivot_value = (model.low[pivot] as i128).checked_add(remainder / coefficient)
+                .and_then(|val| isize::try_from(val).ok());
+            match pivot_value {
+                Some(val) if self.variables[pivot].range().contains(&val) => {
+                    assignment[pivot] = val;
+                    solutions.push(assignment);
+                },
+                _ => (),
+            }
+        }
+        // Solutions come out in a different order than a brute force would visit them
+        solutions.sort_by_key(|assignment| self.index(assignment));
+        Ok(solutions.into_iter().find(|assignment| {
+            // The probes could have been fooled; confirm with a real run
+            self.evaluate(assignment).ok().and_then(|outcome| outcome.observe(observable)) == Some(target)
+        }))
+    }
+    fn index(&self, assignment: &[isize]) -> usize {
+        self.variables.iter().zip(assignment).fold(0, |index, (var, val)| {
+            index.saturating_mul(var.size().unwrap_or(usize::MAX)).saturating_add(val.wrapping_sub(*var.range().start()) as usize)
+        })
+    }
+}
+fn too_many() -> Error {
+    Error::BadInput {msg: "More assignments to search than can be counted".to_string()}
+}
+struct LinearModel {
+    low: Vec<isize>,
+    constant: isize,
+    coefficients: Vec<isize>,
+}
+impl LinearModel {
+    // In i128, and None past even that, so far-flung ranges can't overflow
+    fn predict(&self, assignment: &[isize]) -> Option<i128> {
+        self.coefficients.iter().zip(assignment.iter().zip(&self.low))
+            .try_fold(self.constant as i128, |sum, (coefficient, (val, low))| {
+                (*coefficient as i128).checked_mul(*val as i128 - *low as i128).and_then(|term| sum.checked_add(term))
+            })
+    }
+}
+
+#[cfg(test)]
+fn day02_style_program() -> Vec<isize> {
+    // memory[0] = noun * 100 + verb, with the noun at address 1 and the verb at address 6
+    vec![1102,0,100,0, 1001,0,0,0, 99]
+}
+#[test]
+fn test_brute_force_day02_style() {
+    let mut search = Search::new(day02_style_program(), vec![
+        Variable::Memory {addr: 1, range: 0..=99},
+        Variable::Memory {addr: 6, range: 0..=99},
+    ]);
+    search.set_threads(4);
+    let answer = search.solve_for(Observable::Memory(0), 1234, Strategy::BruteForce).unwrap();
+    assert_eq!(answer, Some(vec![12,34]));
+    let linear = search.solve_for(Observable::Memory(0), 1234, Strategy::Linear).unwrap();
+    assert_eq!(linear, Some(vec![12,34]));
+}
+#[test]
+fn test_nonlinear_falls_back() {
+    // output = input * input
+    let prog = vec![3,9,2,9,9,10,4,10,99,0,0];
+    let search = Search::new(prog, vec![Variable::Input {index: 0, range: -10..=10}]);
+    assert_eq!(search.solve_for(Observable::LastOutput, 49, Strategy::Linear).unwrap(), Some(vec![-7]));
+    assert_eq!(search.find_all(|outcome| outcome.observe(Observable::LastOutput) == Some(49)).unwrap(), vec![vec![-7], vec![7]]);
+    // output = input, over every isize
+    let search = Search::new(vec![3,5,4,5,99,0], vec![Variable::Input {index: 0, range: isize::MIN..=isize::MAX}]);
+    assert_eq!(search.solve_for(Observable::LastOutput, -5, Strategy::Linear).unwrap(), Some(vec![-5]));
+    assert_eq!(search.solve_for(Observable::LastOutput, isize::MAX, Strategy::Linear).unwrap(), Some(vec![isize::MAX]));
+}
+#[test]
+fn test_runaway_and_oversized() {
+    // Outputs the input, but loops forever on a 3
+    let prog = vec![3,13, 1008,13,3,12, 1005,12,6, 4,13, 99, 0, 0];
+    let mut search = Search::new(prog.clone(), vec![Variable::Input {index: 0, range: 0..=9}]);
+    search.set_max_steps(1000);
+    assert!(matches!(search.evaluate(&[3]), Err(Error::StepLimit {steps: 1000})));
+    let small = |outcome: &Outcome| outcome.observe(Observable::LastOutput).is_some_and(|out| out < 5);
+    assert_eq!(search.find_all(small).unwrap(), vec![vec![0], vec![1], vec![2], vec![4]]);
+    let huge = Variable::Input {index: 0, range: isize::MIN..=isize::MAX};
+    let search = Search::new(prog, vec![huge.clone(), huge]);
+    assert_eq!(search.size(), None);
+    assert!(search.find_first(|_| true).is_err());
+}
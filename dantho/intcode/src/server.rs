@@ -0,0 +1,318 @@
+//! Host Intcode machines on a localhost socket, one connection per machine.
+//! Each connection speaks the same line protocol as `intcode run` (see stream.rs), so anything
+//! that can open a socket can drive the day13 arcade or the day25 adventure:
+//! ```text
+//! intcode serve ../day25/input.txt --tcp 127.0.0.1:2019 --ascii
+//! nc 127.0.0.1 2019
+//! ```
+//! With `--network N` the server instead hosts a day23 network once N connections are in: the
+//! Nth connection gets machine N, which first reads its own address and reads -1 whenever nothing
+//! is queued.  The server routes each (dest, x, y) packet to machine dest, or to the NAT at 255,
+//! which wakes machine 0 with the last packet it got whenever the whole network goes idle.  A
+//! connection sees its machine's output as usual, and anything it sends is queued for that machine.
+
+use std::collections::VecDeque;
+use std::net::{TcpListener, TcpStream, Shutdown};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use futures::task::{Context, Poll};
+use crate::{Error, Intcode};
+use crate::io::{Input, Output};
+use crate::stream::{self, StreamFormat, StreamOptions};
+
+#[derive(Debug,Clone)]
+pub enum Endpoint {
+    Tcp(String),  // e.g. "127.0.0.1:2019"
+    #[cfg(unix)]
+    Unix(String), // socket file path
+}
+#[derive(Debug,Clone)]
+pub struct ServerConfig {
+    pub endpoint: Endpoint,
+    pub options: StreamOptions,
+    pub network_size: Option<usize>, // None: a fresh machine per connection, forever
+}
+// Both socket flavours need the same three things from a connection
+trait Connection: std::io::Read + std::io::Write + Send + Sized + 'static {
+    fn duplicate(&self) -> std::io::Result<Self>;
+    fn close(&self);
+    fn peer(&self) -> String;
+}
+impl Connection for TcpStream {
+    fn duplicate(&self) -> std::io::Result<Self> { self.try_clone() }
+    fn close(&self) { let _ = self.shutdown(Shutdown::Both); }
+    fn peer(&self) -> String { self.peer_addr().map(|a| a.to_string()).unwrap_or_default() }
+}
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn duplicate(&self) -> std::io::Result<Self> { self.try_clone() }
+    fn close(&self) { let _ = self.shutdown(Shutdown::Both); }
+    fn peer(&self) -> String { "unix socket peer".to_string() }
+}
+pub fn serve(prog: Vec<isize>, config: &ServerConfig) -> Result<(), Error> {
+    match &config.endpoint {
+        Endpoint::Tcp(addr) => {
+            let listener = TcpListener::bind(addr).map_err(|e| comms(format!("Failure binding {}: {}", addr, e)))?;
+            eprintln!("Intcode server listening on tcp {}", addr);
+            accept_all(prog, config, listener.incoming())
+        },
+        #[cfg(unix)]
+        Endpoint::Unix(path) => {
+            let _ = std::fs::remove_file(path); // stale socket from an earlier run
+            let listener = UnixListener::bind(path).map_err(|e| comms(format!("Failure binding {}: {}", path, e)))?;
+            eprintln!("Intcode server listening on unix {}", path);
+            accept_all(prog, config, listener.incoming())
+        },
+    }
+}
+fn accept_all<C, I>(prog: Vec<isize>, config: &ServerConfig, incoming: I) -> Result<(), Error>
+    where C: Connection, I: Iterator<Item=std::io::Result<C>>
+{
+    let mut machines = Vec::new();
+    let mut connections = Vec::new();
+    let mut attached = 0; // only counts up, so no two machines share an id
+    for conn in incoming {
+        let conn = match conn {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("Failure accepting connection: {}", e);
+                continue;
+            },
+        };
+        let machine_id = attached;
+        attached += 1;
+        eprintln!("Machine {} attached to {}", machine_id, conn.peer());
+        match config.network_size {
+            Some(size) => {
+                connections.push(conn);
+                if connections.len() == size {
+                    return host_network(prog, config, connections);
+                }
+            },
+            None => {
+                // Forget the machines that have finished, or a long-lived server piles them up
+                machines.retain(|machine: &thread::JoinHandle<_>| !machine.is_finished());
+                let (prog, options) = (prog.clone(), config.options.clone());
+                machines.push(thread::spawn(move || {
+                    let result = host_machine(prog, conn, &options);
+                    if let Err(e) = &result {
+                        eprintln!("Machine {} stopped: {:?}", machine_id, e);
+                    }
+                }));
+            },
+        }
+    }
+    for machine in machines {
+        let _ = machine.join();
+    }
+    Ok(())
+}
+fn host_machine<C: Connection>(prog: Vec<isize>, conn: C, options: &StreamOptions) -> Result<(), Error> {
+    let reader = conn.duplicate().map_err(|e| comms(format!("Failure cloning connection: {}", e)))?;
+    let writer = conn.duplicate().map_err(|e| comms(format!("Failure cloning connection: {}", e)))?;
+    let result = stream::run_streams(prog, BufReader::new(reader), writer, options);
+    // Halted (or failed): hang up, which also unblocks the feeder thread still reading the socket
+    conn.close();
+    result
+}
+
+const NAT: isize = 255;
+/// Everything the machines of a network share
+struct Network {
+    queues: Vec<VecDeque<isize>>,
+    idle_reads: Vec<usize>, // -1s read since the machine last sent or received anything
+    running: Vec<bool>,
+    nat: Option<(isize, isize)>,
+    nat_sent_y: Option<isize>,
+    nat_repeated: bool, // has it sent the same Y twice in a row yet?
+}
+impl Network {
+    fn route(&mut self, from: usize, dest: isize, x: isize, y: isize) {
+        self.idle_reads[from] = 0;
+        if dest == NAT {
+            if self.nat.is_none() {
+                eprintln!("NAT got its first packet: X={} Y={}", x, y);
+            }
+            self.nat = Some((x, y));
+        } else if (0..self.queues.len() as isize).contains(&dest) {
+            self.queues[dest as usize].extend(&[x, y]);
+        } else {
+            eprintln!("Machine {} sent to {}, which isn't on the network", from, dest);
+        }
+    }
+    /// Nothing queued and every machine still running has read -1 at least twice in a row
+    fn idle(&self) -> bool {
+        self.queues.iter().all(|queue| queue.is_empty())
+            && self.running.iter().zip(&self.idle_reads).all(|(running, idle_reads)| !running || *idle_reads >= 2)
+    }
+    fn wake(&mut self) {
+        if let Some((x, y)) = self.nat {
+            if self.nat_sent_y == Some(y) && !self.nat_repeated {
+                self.nat_repeated = true;
+                eprintln!("NAT sent machine 0 Y={} twice in a row", y);
+            }
+            self.nat_sent_y = Some(y);
+            self.queues[0].extend(&[x, y]);
+            self.idle_reads.iter_mut().for_each(|idle_reads| *idle_reads = 0);
+        }
+    }
+}
+struct NetworkInput {
+    id: usize,
+    network: Arc<Mutex<Network>>,
+}
+impl Input for NetworkInput {
+    fn poll_input(&mut self, _cx: &mut Context<'_>) -> Poll<Option<isize>> {
+        let mut network = self.network.lock().unwrap();
+        match network.queues[self.id].pop_front() {
+            Some(val) => {
+                network.idle_reads[self.id] = 0;
+                Poll::Ready(Some(val))
+            },
+            None => {
+                network.idle_reads[self.id] += 1;
+                drop(network);
+                thread::sleep(Duration::from_millis(1)); // rather than spin
+                Poll::Ready(Some(-1))
+            },
+        }
+    }
+}
+struct NetworkOutput<W> {
+    id: usize,
+    network: Arc<Mutex<Network>>,
+    packet: Vec<isize>,
+    writer: W,
+    format: StreamFormat,
+}
+impl<W: Write + Send> Output for NetworkOutput<W> {
+    fn send(&mut self, val: isize) -> Result<(), Error> {
+        stream::write_value(&mut self.writer, val, self.format)?;
+        self.packet.push(val);
+        if let [dest, x, y] = self.packet[..] {
+            self.network.lock().unwrap().route(self.id, dest, x, y);
+            self.packet.clear();
+        }
+        Ok(())
+    }
+}
+fn host_network<C: Connection>(prog: Vec<isize>, config: &ServerConfig, connections: Vec<C>) -> Result<(), Error> {
+    let size = connections.len();
+    let queues = (0..size).map(|id| Some(id as isize).into_iter().chain(config.options.boot_input.iter().cloned()).collect()).collect();
+    let network = Arc::new(Mutex::new(Network { queues, idle_reads: vec![0; size], running: vec![true; size], nat: None, nat_sent_y: None, nat_repeated: false }));
+    let format = config.options.format;
+    let mut machines = Vec::new();
+    for (id, conn) in connections.into_iter().enumerate() {
+        let reader = conn.duplicate().map_err(|e| comms(format!("Failure cloning connection: {}", e)))?;
+        let writer = conn.duplicate().map_err(|e| comms(format!("Failure cloning connection: {}", e)))?;
+        // What the client sends joins the machine's queue
+        let client_network = network.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                match line.map_err(|e| comms(e.to_string())).and_then(|line| stream::parse_line(&line, format)) {
+                    Ok(vals) => client_network.lock().unwrap().queues[id].extend(vals),
+                    Err(e) => return eprintln!("Machine {} input: {:?}", id, e),
+                }
+            }
+        });
+        let input = NetworkInput { id, network: network.clone() };
+        let output = NetworkOutput { id, network: network.clone(), packet: Vec::new(), writer, format };
+        let (prog, machine_network) = (prog.clone(), network.clone());
+        machines.push(thread::spawn(move || {
+            if let Err(e) = Intcode::with_io(prog, input, output).run_blocking() {
+                eprintln!("Machine {} stopped: {:?}", id, e);
+            }
+            conn.close();
+            machine_network.lock().unwrap().running[id] = false;
+        }));
+    }
+    eprintln!("Network of {} machines running", size);
+    // The NAT, watching for the network to go quiet
+    loop {
+        thread::sleep(Duration::from_millis(5));
+        let mut network = network.lock().unwrap();
+        if !network.running.contains(&true) {
+            break;
+        }
+        if network.idle() {
+            network.wake();
+        }
+    }
+    for machine in machines {
+        let _ = machine.join();
+    }
+    Ok(())
+}
+fn comms(msg: String) -> Error {
+    Error::ComputerComms {msg}
+}
+
+// A connection made of two Vecs: what the client sent, and what the machine wrote back
+#[cfg(test)]
+#[derive(Clone,Default)]
+struct MemoryConnection {
+    sent: Arc<Mutex<std::io::Cursor<Vec<u8>>>>,
+    received: Arc<Mutex<Vec<u8>>>,
+}
+#[cfg(test)]
+impl MemoryConnection {
+    fn new(sent: &str) -> Self {
+        MemoryConnection { sent: Arc::new(Mutex::new(std::io::Cursor::new(sent.as_bytes().to_vec()))), received: Arc::default() }
+    }
+    fn received(&self) -> String {
+        String::from_utf8(self.received.lock().unwrap().clone()).unwrap()
+    }
+}
+#[cfg(test)]
+impl std::io::Read for MemoryConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.sent.lock().unwrap().read(buf)
+    }
+}
+#[cfg(test)]
+impl Write for MemoryConnection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.received.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+#[cfg(test)]
+impl Connection for MemoryConnection {
+    fn duplicate(&self) -> std::io::Result<Self> { Ok(self.clone()) }
+    fn close(&self) {}
+    fn peer(&self) -> String { "memory".to_string() }
+}
+#[test]
+fn test_server() {
+    let config = |network_size| ServerConfig {
+        endpoint: Endpoint::Tcp(String::new()),
+        options: StreamOptions::new(StreamFormat::Numeric),
+        network_size,
+    };
+    // A machine per connection, doubling its input until a 0; a failed accept doesn't stop the server
+    let doubler = vec![3,20, 1006,20,14, 1002,20,2,21, 4,21, 1105,1,0, 99];
+    let (first, second) = (MemoryConnection::new("1\n2\n0\n"), MemoryConnection::new("5\n0\n"));
+    let failed = std::io::Error::other("no more file descriptors");
+    let incoming = vec![Ok(first.clone()), Err(failed), Ok(second.clone())];
+    accept_all(doubler, &config(None), incoming.into_iter()).unwrap();
+    assert_eq!((first.received(), second.received()), ("2\n4\n".to_string(), "10\n".to_string()));
+
+    // Machine 0 sends (7, 8) to machine 1, which sends their sum on to the NAT and halts.  Once
+    // machine 0 is left reading -1, the NAT hands it the sum, which it sends to itself and halts.
+    let network = vec![
+        3,60, 1005,60,29, 104,1, 104,7, 104,8,
+        3,61, 1008,61,-1,63, 1005,63,11, 3,62, 104,0, 4,61, 4,62, 99,
+        3,61, 1008,61,-1,63, 1005,63,29, 3,62, 1,61,62,63, 104,255, 4,63, 104,0, 99,
+        0,0,0,0,0,0,0,0,0, 0,0,0,0,
+    ];
+    let machines = vec![MemoryConnection::new(""), MemoryConnection::new("")];
+    accept_all(network, &config(Some(2)), machines.clone().into_iter().map(Ok)).unwrap();
+    assert_eq!(machines[0].received(), "1\n7\n8\n0\n15\n0\n");
+    assert_eq!(machines[1].received(), "255\n15\n0\n");
+}
@@ -0,0 +1,157 @@
+//! Unix pipe streaming -- one value per line in on stdin, one value per line out on stdout.
+//! Every output is flushed the moment the computer produces it, so machines can be chained.
+//! The day07 amplifier feedback loop, from the shell:
+//! ```text
+//! mkfifo ring
+//! (echo 9; echo 0; cat ring) | intcode run amp.txt | (echo 8; cat) | intcode run amp.txt \
+//!   | (echo 7; cat) | intcode run amp.txt | (echo 6; cat) | intcode run amp.txt \
+//!   | (echo 5; cat) | intcode run amp.txt | tee ring | tail -n 1
+//! ```
+
+use std::io::prelude::*;
+use std::fs::File;
+use std::io::{stdin, stdout, BufReader, BufWriter};
+use std::sync::mpsc;
+use std::thread;
+use futures::prelude::*;
+use futures::channel::mpsc::{channel,Sender,Receiver};
+use futures::executor::block_on;
+use futures::join;
+use crate::{Error, Intcode};
+use crate::record::InputRecorder;
+use crate::coverage::Coverage;
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum StreamFormat {
+    Numeric, // "42\n" per value
+    Ascii,   // text lines in (newline included), chars out; non-ASCII values still printed as numbers
+}
+#[derive(Debug,Clone)]
+pub struct StreamOptions {
+    pub format: StreamFormat,
+    pub boot_input: Vec<isize>,    // queued ahead of anything read from the stream, e.g. a day23 network address
+    pub idle_input: Option<isize>, // supplied when the computer reads with nothing queued (day23 uses -1)
+    pub record_to: Option<String>, // input log file, see record.rs
+    pub coverage_to: Option<String>, // coverage report file, see coverage.rs
+    pub trace_to: Option<String>,    // every instruction as it runs
+}
+impl StreamOptions {
+    pub fn new(format: StreamFormat) -> Self {
+        StreamOptions { format, boot_input: Vec::new(), idle_input: None, record_to: None, coverage_to: None, trace_to: None }
+    }
+}
+/// Run `prog` with stdin as its input stream and stdout as its output stream, until it halts
+pub fn run_stdio(prog: Vec<isize>, options: &StreamOptions) -> Result<(), Error> {
+    run_streams(prog, BufReader::new(stdin()), stdout(), options)
+}
+/// Run `prog` reading input lines from `reader` and writing outputs to `writer`, until it halts
+pub fn run_streams<R, W>(prog: Vec<isize>, reader: R, writer: W, options: &StreamOptions) -> Result<(), Error>
+    where R: BufRead + Send + 'static, W: Write
+{
+    const BUFFER_SIZE: usize = 10;
+    let (mut feeder_tx, computer_rx) = channel::<isize>(BUFFER_SIZE.max(options.boot_input.len()));
+    let (computer_tx, printer_rx) = channel::<isize>(BUFFER_SIZE);
+    for val in &options.boot_input {
+        block_on(feeder_tx.send(*val)).map_err(|_| Error::ComputerComms {msg: "Failure queueing boot input.".to_string()})?;
+    }
+    // Reads block, so the feeder gets its own thread rather than a seat in the join! below.
+    // It reports how it ended before hanging up, so a bad line is what gets reported rather than
+    // the computer finding its input ended.  (Not joined: stdin may stay open after a halt.)
+    let format = options.format;
+    let (feeder_status_tx, feeder_status) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = feeder_status_tx.send(feed_lines(reader, &mut feeder_tx, format));
+    });
+    // stderr, so the boot message never ends up in a pipe with our output
+    eprintln!("Intcode Model 2019_26.1 booting...");
+    let image = if options.coverage_to.is_some() {prog.clone()} else {Vec::new()};
+    let mut computer = Intcode::new(prog, computer_rx, computer_tx);
+    computer.set_idle_input(options.idle_input);
+    if let Some(filename) = &options.record_to {
+        computer.set_recorder(Some(InputRecorder::create(filename)?));
+    }
+    if options.coverage_to.is_some() {
+        computer.set_coverage(Some(Coverage::new()));
+    }
+    if let Some(filename) = &options.trace_to {
+        let fd = File::create(filename).map_err(|e| Error::BadInput {msg: format!("Failure creating {}: {}", filename, e)})?;
+        computer.set_trace(Some(Box::new(BufWriter::new(fd))));
+    }
+    let (computer_result, printer_result) = block_on(async {
+        join!(computer.run_to_halt(), print_values(printer_rx, writer, format))
+    });
+    // Coverage is worth having even when the run ended badly, e.g. stdin closed mid-game
+    if let (Some(filename), Some(coverage)) = (&options.coverage_to, computer.take_coverage()) {
+        coverage.save_report(filename, &image)?;
+    }
+    if let Ok(Err(e)) = feeder_status.try_recv() {
+        return Err(e);
+    }
+    computer_result?;
+    printer_result
+}
+fn feed_lines<R: BufRead>(reader: R, tx: &mut Sender<isize>, format: StreamFormat) -> Result<(), Error> {
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| Error::BadInput {msg: format!("Failure reading input: {}", e)})?;
+        let vals = parse_line(&line, format).map_err(|e| match e {
+            Error::BadInput {msg} => Error::BadInput {msg: format!("Input line {}: {}", line_no + 1, msg)},
+            e => e,
+        })?;
+        for val in vals {
+            if block_on(tx.send(val)).is_err() {
+                // Computer halted; nobody is listening anymore
+                return Ok(());
+            }
+        }
+    }
+    Ok(()) // The Sender drops after this -- a Read with nothing queued then reports end-of-stream
+}
+async fn print_values<W: Write>(mut rx: Receiver<isize>, mut out: W, format: StreamFormat) -> Result<(), Error> {
+    while let Some(val) = rx.next().await {
+        write_value(&mut out, val, format)?;
+    }
+    Ok(())
+}
+// ASCII output as characters, except values outside ASCII (day17's and day25's answers)
+pub(crate) fn write_value<W: Write>(out: &mut W, val: isize, format: StreamFormat) -> Result<(), Error> {
+    let written = match format {
+        StreamFormat::Ascii if (0..128).contains(&val) => write!(out, "{}", val as u8 as char),
+        StreamFormat::Ascii => writeln!(out, "{}", val),
+        StreamFormat::Numeric => writeln!(out, "{}", val),
+    };
+    // Fails when the downstream end of the pipe went away (e.g. `| head`)
+    written.and_then(|_| out.flush()).map_err(|e| Error::ComputerComms {msg: format!("Failure writing output: {}", e)})
+}
+/// One line of input text as Intcode input values. Blank numeric lines are skipped.
+pub fn parse_line(line: &str, format: StreamFormat) -> Result<Vec<isize>, Error> {
+    match format {
+        StreamFormat::Ascii => Ok(line.chars().chain(Some('\n')).map(|ch| ch as isize).collect()),
+        StreamFormat::Numeric => line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<isize>().map_err(|_| Error::BadInput {msg: format!("Not an integer: '{}'", s)}))
+            .collect(),
+    }
+}
+
+#[test]
+fn test_parse_line() {
+    assert_eq!(parse_line(" 42 ", StreamFormat::Numeric).unwrap(), vec![42]);
+    assert_eq!(parse_line("9,-1", StreamFormat::Numeric).unwrap(), vec![9,-1]);
+    assert_eq!(parse_line("", StreamFormat::Numeric).unwrap(), vec![]);
+    assert!(parse_line("north", StreamFormat::Numeric).is_err());
+    assert_eq!(parse_line("inv", StreamFormat::Ascii).unwrap(), vec![105,110,118,10]);
+}
+#[test]
+fn test_bad_input_line() {
+    // Reads two values and echoes their sum; the second line isn't a number
+    let prog = vec![3,13, 3,14, 1,13,14,15, 4,15, 99, 0,0, 0,0,0];
+    let mut out = Vec::new();
+    let input = std::io::Cursor::new(b"3\nfour\n".to_vec());
+    match run_streams(prog.clone(), input, &mut out, &StreamOptions::new(StreamFormat::Numeric)) {
+        Err(Error::BadInput {msg}) => assert_eq!(msg, "Input line 2: Not an integer: 'four'"),
+        other => panic!("Expected a bad line 2, got {:?}", other),
+    }
+    let input = std::io::Cursor::new(b"3\n4\n".to_vec());
+    run_streams(prog, input, &mut out, &StreamOptions::new(StreamFormat::Numeric)).unwrap();
+    assert_eq!(out, b"7\n");
+}
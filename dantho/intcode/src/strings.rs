@@ -0,0 +1,245 @@
+//! String tables for ASCII programs.
+//! Some programs keep their prompts as plain characters in the image (day17's "Main:", day21's
+//! "Walking...").  Others decode them on the way out (day25 adds the index and a key to each
+//! character), so a scan of the image can't see them.  For those, a bounded emulation traces where
+//! every output value came from: each word remembers the image addresses it was computed from, and a
+//! run of characters whose origins step through consecutive addresses is a string stored there.
+//! ```text
+//! intcode strings ../day25/input.txt --input north
+//!     47: decoded "\n\nDoors here lead:\n"
+//!   3132: decoded "Hull Breach"
+//! ```
+
+use crate::OpCode;
+use crate::disasm::{self, Param};
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Source {
+    Literal, // the characters themselves are in the image
+    Decoded, // seen only in the output, traced back to these addresses
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct TextRegion {
+    pub addr: usize,
+    pub text: String,
+    pub source: Source,
+}
+impl TextRegion {
+    pub fn format(&self) -> String {
+        let source = match self.source {Source::Literal => "literal", Source::Decoded => "decoded"};
+        format!("{:>6}: {} {:?}", self.addr, source, self.text)
+    }
+}
+/// Runs of printable words in the image that look like prose, at least `min_len` characters long
+pub fn literal_strings(prog: &[isize], min_len: usize) -> Vec<TextRegion> {
+    let mut regions = Vec::new();
+    let mut start = 0;
+    for addr in 0..=prog.len() {
+        if prog.get(addr).is_some_and(|val| is_text(*val)) {
+            continue;
+        }
+        let text: String = prog[start..addr].iter().map(|val| *val as u8 as char).collect();
+        if text.len() >= min_len && looks_like_prose(&text) {
+            regions.push(TextRegion { addr: start, text, source: Source::Literal });
+        }
+        start = addr + 1;
+    }
+    regions
+}
+/// Strings the program prints in its first `max_steps` instructions, given `inputs`.
+/// The emulation stops early at Halt, when it runs out of input, or on anything illegal.
+pub fn traced_strings(prog: &[isize], inputs: &[isize], max_steps: usize, min_len: usize) -> Vec<TextRegion> {
+    let mut tracer = Tracer::new(prog);
+    tracer.run(inputs, max_steps);
+    let mut regions = Vec::new();
+    // Each track is (first origin, latest origin) of a string that is still being printed
+    let mut tracks: Vec<(usize, usize)> = Vec::new();
+    let mut text = String::new();
+    for (val, origins) in tracer.outputs.iter().chain(std::iter::once(&(0, Vec::new()))) {
+        let continued: Vec<(usize, usize)> = tracks.iter()
+            .filter(|(_, latest)| is_text(*val) && origins.contains(&(latest + 1)))
+            .map(|(first, latest)| (*first, latest + 1))
+            .collect();
+        if continued.is_empty() {
+            if let Some((first, _)) = tracks.first() {
+                if text.chars().count() >= min_len {
+                    let literal = text.chars().zip(&prog[*first..]).all(|(ch, val)| ch as isize == *val);
+                    let source = if literal {Source::Literal} else {Source::Decoded};
+                    regions.push(TextRegion { addr: *first, text: text.clone(), source });
+                }
+            }
+            text.clear();
+            tracks = if is_text(*val) {origins.iter().map(|origin| (*origin, *origin)).collect()} else {Vec::new()};
+        } else {
+            tracks = continued;
+        }
+        if !tracks.is_empty() {
+            text.push(*val as u8 as char);
+        }
+    }
+    regions
+}
+/// Literal strings and traced strings together, by address.  Where the trace shows that a
+/// printable-looking run is really encoded text, the decoded version replaces it.
+pub fn string_table(prog: &[isize], inputs: &[isize], max_steps: usize, min_len: usize) -> Vec<TextRegion> {
+    let literal = literal_strings(prog, min_len);
+    let mut traced = traced_strings(prog, inputs, max_steps, min_len);
+    traced.sort_by_key(|region| region.addr);
+    traced.dedup();
+    let overlaps = |a: &TextRegion, b: &TextRegion| a.addr < b.addr + b.text.len() && b.addr < a.addr + a.text.len();
+    let mut table: Vec<TextRegion> = literal.iter()
+        .filter(|region| !traced.iter().any(|t| t.source == Source::Decoded && overlaps(t, region)))
+        .cloned()
+        .collect();
+    for region in traced {
+        let known = region.source == Source::Literal && literal.iter().any(|l| overlaps(l, &region));
+        if !known {
+            table.push(region);
+        }
+    }
+    table.sort_by_key(|region| region.addr);
+    table
+}
+// Encoded data is often printable too, but it rarely has spaces, is full of symbols, and is short on vowels
+fn looks_like_prose(text: &str) -> bool {
+    let wordy = text.chars().filter(|ch| ch.is_ascii_alphanumeric() || " \n.,:;!?'-".contains(*ch)).count();
+    let letters = text.chars().filter(|ch| ch.is_ascii_alphabetic()).count();
+    let vowels = text.chars().filter(|ch| "aeiouAEIOU".contains(*ch)).count();
+    text.contains([' ', '\n']) && wordy * 5 >= text.len() * 4 && vowels * 5 >= letters
+}
+fn is_text(val: isize) -> bool {
+    val == 10 || (32..127).contains(&val)
+}
+
+// Where a value came from: the image addresses it was computed from, first operand first
+type Origins = Vec<usize>;
+const MAX_ORIGINS: usize = 8;
+const MAX_MEMORY: usize = 1 << 20;
+struct Tracer {
+    memory: Vec<isize>,
+    origins: Vec<Origins>,
+    pc: usize,
+    relative_base: isize,
+    outputs: Vec<(isize, Origins)>,
+}
+impl Tracer {
+    fn new(prog: &[isize]) -> Self {
+        let origins = (0..prog.len()).map(|addr| vec![addr]).collect();
+        Tracer { memory: prog.to_vec(), origins, pc: 0, relative_base: 0, outputs: Vec::new() }
+    }
+    fn run(&mut self, inputs: &[isize], max_steps: usize) {
+        let mut inputs = inputs.iter();
+        for _ in 0..max_steps {
+            let instruction = match disasm::decode(&self.memory, self.pc) {
+                Some(instruction) => instruction,
+                None => return,
+            };
+            let next = self.pc + instruction.size();
+            let mut operands = Vec::new();
+            for (i, param) in instruction.params.iter().enumerate() {
+                match self.operand(*param, self.pc + 1 + i) {
+                    Some(operand) => operands.push(operand),
+                    None => return,
+                }
+            }
+            let dest = match instruction.params.last() {
+                Some(param) if instruction.opcode.writes_last() => match self.address(*param) {
+                    Some(addr) => addr,
+                    None => return,
+                },
+                _ => 0,
+            };
+            use OpCode::*;
+            match instruction.opcode {
+                Add | Multiply => {
+                    let ((a, a_origins), (b, b_origins)) = (&operands[0], &operands[1]);
+                    let val = if instruction.opcode == Add {a.checked_add(*b)} else {a.checked_mul(*b)};
+                    let origins = a_origins.iter().chain(b_origins).cloned().fold(Vec::new(), |mut all, origin| {
+                        if !all.contains(&origin) && all.len() < MAX_ORIGINS {
+                            all.push(origin);
+                        }
+                        all
+                    });
+                    match val {
+                        Some(val) => self.store(dest, val, origins),
+                        None => return,
+                    }
+                },
+                CompareLT | CompareEQ => {
+                    let (a, b) = (operands[0].0, operands[1].0);
+                    let val = if instruction.opcode == CompareLT {a < b} else {a == b};
+                    self.store(dest, val as isize, Vec::new());
+                },
+                Read => match inputs.next() {
+                    Some(val) => self.store(dest, *val, Vec::new()),
+                    None => return,
+                },
+                Write => self.outputs.push(operands[0].clone()),
+                BranchNE | BranchEQ => {
+                    let taken = (operands[0].0 != 0) == (instruction.opcode == BranchNE);
+                    if taken {
+                        if operands[1].0 < 0 {
+                            return;
+                        }
+                        self.pc = operands[1].0 as usize;
+                        continue;
+                    }
+                },
+                AdjustBase => self.relative_base += operands[0].0,
+                Halt | Extension(_) => return,
+            }
+            self.pc = next;
+        }
+    }
+    // The value of a parameter, and where it came from.  An immediate came from its own word.
+    fn operand(&self, param: Param, param_addr: usize) -> Option<(isize, Origins)> {
+        match param {
+            Param::Immediate(val) => Some((val, self.origins[param_addr].clone())),
+            _ => {
+                let addr = self.address(param)?;
+                Some((*self.memory.get(addr).unwrap_or(&0), self.origins.get(addr).cloned().unwrap_or_default()))
+            },
+        }
+    }
+    fn address(&self, param: Param) -> Option<usize> {
+        let addr = match param {
+            Param::Position(addr) => addr,
+            Param::Relative(offset) => self.relative_base + offset,
+            Param::Immediate(_) => return None,
+        };
+        if (0..MAX_MEMORY as isize).contains(&addr) {Some(addr as usize)} else {None}
+    }
+    fn store(&mut self, addr: usize, val: isize, origins: Origins) {
+        if addr >= self.memory.len() {
+            self.memory.resize(addr + 1, 0);
+            self.origins.resize(addr + 1, Vec::new());
+        }
+        self.memory[addr] = val;
+        self.origins[addr] = origins;
+    }
+}
+
+#[test]
+fn test_literal_strings() {
+    let prog = vec![1105,1,9, 3,72,105,33,10, 0,99, 72,105];
+    let regions = literal_strings(&prog, 4);
+    assert_eq!(regions, vec![TextRegion { addr: 4, text: "Hi!\n".to_string(), source: Source::Literal }]);
+}
+#[test]
+fn test_traced_strings() {
+    // Print the 3 characters stored at 30.., each stored as char - 5 - index
+    let prog = vec![
+        1001,30,5,40,   // 0: [40] = [30+i] + 5
+        1,40,41,40,     // 4: [40] += i
+        4,40,           // 8: out [40]
+        1001,1,1,1,     // 10: next source address
+        1001,41,1,41,   // 14: i += 1
+        1007,41,3,42,   // 18: [42] = i < 3
+        1005,42,0,      // 22: loop while i < 3
+        99,0,0,0,0,     // 25
+        67,99,26,       // 30: "Hi!" encoded
+    ];
+    assert!(literal_strings(&prog, 4).is_empty());
+    let regions = traced_strings(&prog, &[], 1000, 3);
+    assert_eq!(regions, vec![TextRegion { addr: 30, text: "Hi!".to_string(), source: Source::Decoded }]);
+}
@@ -0,0 +1,270 @@
+//! Threaded code: a program translated once, ahead of time, into one closure per address, each with
+//! its opcode, modes and operands already decoded.  Running it is a loop of indirect calls with no
+//! fetch or decode, which pays off where one program is run over and over on fresh memory -- day19's
+//! beam probe, one run per point, or a search.
+//! An instruction the program writes over is interpreted from memory, as it is now, for the rest
+//! of that run; the rest of the program stays translated.
+//! ```text
+//! cargo bench --bench threaded     # against run_batch, on day09 and day19
+//! ```
+
+use std::convert::TryFrom;
+use std::slice;
+use crate::{Error, OpCode, MAX_MEMORY};
+
+// Runs one instruction, leaving `machine.pc` at the next
+type Op = Box<dyn Fn(&mut Machine) -> Result<(), Error> + Send + Sync>;
+
+pub struct Threaded {
+    image: Vec<isize>,
+    ops: Vec<Op>,
+    sizes: Vec<usize>, // words covered by ops[addr]
+    step_limit: Option<usize>,
+}
+impl Threaded {
+    pub fn new(prog: &[isize]) -> Self {
+        let image = prog.to_vec();
+        let ops = (0..image.len()).map(|addr| compile(&image, addr)).collect();
+        let sizes = (0..image.len()).map(|addr| size(&image, addr)).collect();
+        Threaded { image, ops, sizes, step_limit: None }
+    }
+    /// Give up on a run, with `Error::StepLimit`, once it has executed `limit` instructions
+    pub fn set_step_limit(&mut self, limit: Option<usize>) {
+        self.step_limit = limit;
+    }
+    /// Like `run_batch`: run a fresh copy of the program to Halt, returning its final memory and
+    /// every output
+    pub fn run(&self, inputs: &[isize]) -> Result<(Vec<isize>, Vec<isize>), Error> {
+        self.run_patched(&[], inputs)
+    }
+    /// `run`, with memory words set first (day02's noun and verb); instructions they land in
+    /// are interpreted
+    pub fn run_patched(&self, pokes: &[(usize, isize)], inputs: &[isize]) -> Result<(Vec<isize>, Vec<isize>), Error> {
+        let mut machine = Machine {
+            mem: self.image.clone(),
+            pc: 0,
+            halted: false,
+            relative_base: 0,
+            inputs: inputs.iter(),
+            outputs: Vec::new(),
+            translated: vec![true; self.image.len()],
+            sizes: &self.sizes,
+        };
+        for (addr, val) in pokes {
+            machine.put(0, *addr, *val)?;
+        }
+        let mut steps = 0;
+        while !machine.halted {
+            if Some(steps) == self.step_limit {
+                return Err(Error::StepLimit { steps });
+            }
+            steps += 1;
+            let pc = machine.pc;
+            match self.ops.get(pc) {
+                Some(op) if machine.translated[pc] => op(&mut machine)?,
+                _ => compile(&machine.mem, pc)(&mut machine)?,
+            }
+        }
+        Ok((machine.mem, machine.outputs))
+    }
+}
+
+struct Machine<'a> {
+    mem: Vec<isize>,
+    pc: usize,
+    halted: bool,
+    relative_base: isize,
+    inputs: slice::Iter<'a, isize>,
+    outputs: Vec<isize>,
+    translated: Vec<bool>, // the op at each address still matches memory
+    sizes: &'a [usize],
+}
+impl Machine<'_> {
+    fn get(&self, pc: usize, src: Src) -> Result<isize, Error> {
+        let addr = match src {
+            Src::Value(val) => return Ok(val),
+            Src::At(addr) => addr,
+            Src::Relative(offset) => address(pc, offset + self.relative_base)?,
+            Src::Bad(addr) => return Err(Error::IllegalAddress { pc, addr }),
+            Src::BadMode(mode) => return Err(Error::IllegalMode { pc, mode }),
+        };
+        Ok(*self.mem.get(addr).unwrap_or(&0))
+    }
+    fn dest(&self, pc: usize, src: Src) -> Result<usize, Error> {
+        match src {
+            Src::At(addr) => Ok(addr),
+            Src::Relative(offset) => address(pc, offset + self.relative_base),
+            Src::Bad(addr) => Err(Error::IllegalAddress { pc, addr }),
+            Src::Value(_) => Err(Error::IllegalMode { pc, mode: 1 }),
+            Src::BadMode(mode) => Err(Error::IllegalMode { pc, mode }),
+        }
+    }
+    fn put(&mut self, pc: usize, addr: usize, val: isize) -> Result<(), Error> {
+        if addr >= self.mem.len() {
+            if addr >= MAX_MEMORY {
+                return Err(Error::IllegalAddress { pc, addr: addr as isize });
+            }
+            self.mem.resize(addr + 1, 0);
+        }
+        if self.mem[addr] != val && addr < self.sizes.len() {
+            // Self-modification: every instruction covering this word goes back to the interpreter
+            for start in addr.saturating_sub(3)..=addr {
+                if start + self.sizes[start] > addr {
+                    self.translated[start] = false;
+                }
+            }
+        }
+        self.mem[addr] = val;
+        Ok(())
+    }
+}
+fn address(pc: usize, addr: isize) -> Result<usize, Error> {
+    usize::try_from(addr).map_err(|_| Error::IllegalAddress { pc, addr })
+}
+
+// A parameter with its mode applied, so far as that can be done ahead of time
+#[derive(Debug,Copy,Clone)]
+enum Src {
+    Value(isize),
+    At(usize),
+    Relative(isize),
+    Bad(isize),     // a negative position
+    BadMode(isize),
+}
+fn src(mode: isize, p: isize) -> Src {
+    match mode {
+        0 => usize::try_from(p).map(Src::At).unwrap_or(Src::Bad(p)),
+        1 => Src::Value(p),
+        2 => Src::Relative(p),
+        _ => Src::BadMode(mode),
+    }
+}
+// Each mode as its own type, so every mix of modes gets a closure with no mode tests left in it
+trait Operand: Copy + Send + Sync + 'static {
+    fn get(self, m: &Machine, pc: usize) -> Result<isize, Error>;
+    fn dest(self, m: &Machine, pc: usize) -> Result<usize, Error>;
+}
+#[derive(Copy,Clone)]
+struct Value(isize);
+#[derive(Copy,Clone)]
+struct At(usize);
+#[derive(Copy,Clone)]
+struct Relative(isize);
+#[derive(Copy,Clone)]
+struct Other(Src); // whatever fails
+impl Operand for Value {
+    fn get(self, _: &Machine, _: usize) -> Result<isize, Error> { Ok(self.0) }
+    fn dest(self, m: &Machine, pc: usize) -> Result<usize, Error> { m.dest(pc, Src::Value(self.0)) }
+}
+impl Operand for At {
+    fn get(self, m: &Machine, _: usize) -> Result<isize, Error> { Ok(*m.mem.get(self.0).unwrap_or(&0)) }
+    fn dest(self, _: &Machine, _: usize) -> Result<usize, Error> { Ok(self.0) }
+}
+impl Operand for Relative {
+    fn get(self, m: &Machine, pc: usize) -> Result<isize, Error> { m.get(pc, Src::Relative(self.0)) }
+    fn dest(self, m: &Machine, pc: usize) -> Result<usize, Error> { m.dest(pc, Src::Relative(self.0)) }
+}
+impl Operand for Other {
+    fn get(self, m: &Machine, pc: usize) -> Result<isize, Error> { m.get(pc, self.0) }
+    fn dest(self, m: &Machine, pc: usize) -> Result<usize, Error> { m.dest(pc, self.0) }
+}
+// `$body` with `$x` bound to `$src` as an Operand of the type for its mode
+macro_rules! operand {
+    ($src:expr, $x:ident => $body:expr) => {
+        match $src {
+            Src::Value(val) => { let $x = Value(val); $body },
+            Src::At(addr) => { let $x = At(addr); $body },
+            Src::Relative(offset) => { let $x = Relative(offset); $body },
+            src => { let $x = Other(src); $body },
+        }
+    };
+}
+fn size(prog: &[isize], addr: usize) -> usize {
+    match OpCode::try_from(prog[addr] % 100) {
+        Ok(op) => 1 + op.arity(),
+        Err(_) => 1,
+    }
+}
+// The instruction at `pc` in `mem` as a closure.  Decoding follows Intcode::run, and so do errors,
+// which are raised when (and only if) the instruction runs.
+fn compile(mem: &[isize], pc: usize) -> Op {
+    use OpCode::*;
+    let word = |addr: usize| *mem.get(addr).unwrap_or(&0);
+    let code = word(pc);
+    let mode = code / 100;
+    let op = match OpCode::try_from(code - mode * 100) {
+        Ok(op) if mode / 1000 == 0 => op,
+        Ok(_) => return Box::new(move |_| Err(Error::IllegalOpcode { code })),
+        Err(_) => return Box::new(move |_| Err(Error::IllegalOpcode { code: code - mode * 100 })),
+    };
+    let a = src(mode % 10, word(pc + 1));
+    let b = src(mode / 10 % 10, word(pc + 2));
+    let c = src(mode / 100 % 10, word(pc + 3));
+    match op {
+        Add => operand!(a, a => operand!(b, b => operand!(c, c => binary(pc, a, b, c, isize::checked_add)))),
+        Multiply => operand!(a, a => operand!(b, b => operand!(c, c => binary(pc, a, b, c, isize::checked_mul)))),
+        CompareLT => operand!(a, a => operand!(b, b => operand!(c, c => binary(pc, a, b, c, |x, y| Some((x < y) as isize))))),
+        CompareEQ => operand!(a, a => operand!(b, b => operand!(c, c => binary(pc, a, b, c, |x, y| Some((x == y) as isize))))),
+        Read => operand!(a, a => Box::new(move |m| {
+            let addr = a.dest(m, pc)?;
+            let val = *m.inputs.next().ok_or_else(|| Error::ComputerComms{msg:"Expecting input, but stream has terminated.".to_string()})?;
+            m.put(pc, addr, val)?;
+            m.pc = pc + 2;
+            Ok(())
+        })),
+        Write => operand!(a, a => Box::new(move |m| {
+            let val = a.get(m, pc)?;
+            m.outputs.push(val);
+            m.pc = pc + 2;
+            Ok(())
+        })),
+        BranchNE => operand!(a, a => operand!(b, b => branch(pc, a, b, |val| val != 0))),
+        BranchEQ => operand!(a, a => operand!(b, b => branch(pc, a, b, |val| val == 0))),
+        AdjustBase => operand!(a, a => Box::new(move |m| {
+            m.relative_base += a.get(m, pc)?;
+            m.pc = pc + 2;
+            Ok(())
+        })),
+        Extension(_) => Box::new(move |_| Err(Error::IllegalOpcode { code })), // not built in, so not decoded
+        Halt => Box::new(|m| {
+            m.halted = true;
+            Ok(())
+        }),
+    }
+}
+fn binary<A: Operand, B: Operand, C: Operand, F>(pc: usize, a: A, b: B, c: C, f: F) -> Op
+    where F: Fn(isize, isize) -> Option<isize> + Send + Sync + 'static
+{
+    Box::new(move |m| {
+        let v1 = a.get(m, pc)?;
+        let v2 = b.get(m, pc)?;
+        let addr = c.dest(m, pc)?;
+        m.put(pc, addr, f(v1, v2).ok_or(Error::Overflow { pc })?)?;
+        m.pc = pc + 4;
+        Ok(())
+    })
+}
+fn branch<A: Operand, B: Operand, F>(pc: usize, a: A, b: B, taken: F) -> Op
+    where F: Fn(isize) -> bool + Send + Sync + 'static
+{
+    Box::new(move |m| {
+        let v1 = a.get(m, pc)?;
+        let v2 = b.get(m, pc)?;
+        m.pc = if taken(v1) { v2 as usize } else { pc + 3 };
+        Ok(())
+    })
+}
+
+#[test]
+fn test_threaded() {
+    let quine = vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+    assert_eq!(Threaded::new(&quine).run(&[]).unwrap().1, quine);
+    // Input doubled, by a Multiply whose immediate operand is patched in from the input
+    let prog = vec![3,3,1102,0,2,9,4,9,99,0];
+    let threaded = Threaded::new(&prog);
+    for input in &[0, 7, -21] {
+        assert_eq!(threaded.run(&[*input]).unwrap(), crate::run_batch(prog.clone(), &[*input]).unwrap());
+    }
+    // The same, patched to triple its input instead
+    assert_eq!(threaded.run_patched(&[(4, 3)], &[7]).unwrap().1, vec![21]);
+}
@@ -0,0 +1,79 @@
+//! Escape codes to stdout, or any other writer.  Drawing is buffered until `flush_frame`, so a
+//! frame appears at once.
+
+use std::io::{stdout, Stdout, Write};
+use crate::Renderer;
+
+const ESC_CLS: &str = "\x1B[2J";
+const ESC_CURSOR_ON: &str = "\x1B[?25h";
+const ESC_CURSOR_OFF: &str = "\x1B[?25l";
+const ESC_COLOR_RESET: &str = "\x1B[0m";
+
+#[derive(Debug)]
+pub struct Ansi<W = Stdout> {
+    out: W,
+    buffer: String,
+    color: Option<u8>, // as last set on the terminal
+    bottom: usize,     // the lowest row drawn on
+}
+impl Ansi {
+    pub fn new() -> Self {
+        Ansi::to(stdout())
+    }
+}
+impl Default for Ansi {
+    fn default() -> Self {
+        Ansi::new()
+    }
+}
+impl<W: Write> Ansi<W> {
+    pub fn to(out: W) -> Self {
+        Ansi { out, buffer: String::new(), color: None, bottom: 0 }
+    }
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+    fn move_to(&mut self, y: usize, x: usize) {
+        self.buffer += &format!("\x1B[{};{}H", y + 1, x + 1);
+        self.bottom = self.bottom.max(y);
+    }
+    fn set_color(&mut self, color: Option<u8>) {
+        if color != self.color {
+            match color {
+                Some(code) => self.buffer += &format!("\x1B[{}m", code),
+                None => self.buffer += ESC_COLOR_RESET,
+            }
+            self.color = color;
+        }
+    }
+}
+impl<W: Write + Send> Renderer for Ansi<W> {
+    fn clear(&mut self) {
+        self.set_color(None);
+        self.buffer += ESC_CLS;
+        self.buffer += ESC_CURSOR_OFF;
+        self.bottom = 0;
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        self.move_to(y, x);
+        self.set_color(color);
+        self.buffer.push(ch);
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        self.move_to(y, x);
+        self.set_color(None);
+        self.buffer += text;
+    }
+    fn flush_frame(&mut self) {
+        self.out.write_all(self.buffer.as_bytes()).unwrap();
+        self.out.flush().unwrap();
+        self.buffer.clear();
+    }
+    fn finish(&mut self) {
+        self.set_color(None);
+        let bottom = self.bottom;
+        self.move_to(bottom + 1, 0);
+        self.buffer += ESC_CURSOR_ON;
+        self.flush_frame();
+    }
+}
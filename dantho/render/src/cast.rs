@@ -0,0 +1,121 @@
+//! Recording as an asciinema v2 `.cast` file: each frame's escape codes, stamped with when it was
+//! shown, so a run can be replayed and shared without the Intcode program.
+//! ```text
+//! cargo run -- --cast breakout.cast
+//! asciinema play breakout.cast
+//! ```
+//! The file is written when drawing finishes, once the picture's size is known for the header.
+
+use std::fs;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::{Ansi, Renderer};
+
+/// What a frame is stamped with
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Clock {
+    Wall,            // the time it was shown
+    Fixed(Duration), // frames evenly spaced, for recordings made headless
+}
+pub struct Cast {
+    path: String,
+    ansi: Ansi<Vec<u8>>,
+    clock: Clock,
+    started: Instant,
+    timestamp: u64,
+    events: Vec<(f64, String)>,
+    width: usize,
+    height: usize,
+}
+impl Cast {
+    pub fn new(path: &str, clock: Clock) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_secs()).unwrap_or(0);
+        Cast {
+            path: path.to_string(),
+            ansi: Ansi::to(Vec::new()),
+            clock,
+            started: Instant::now(),
+            timestamp,
+            events: Vec::new(),
+            width: 1,
+            height: 1,
+        }
+    }
+    /// The file as it would be written now
+    pub fn contents(&self) -> String {
+        let mut text = format!("{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}\n",
+            self.width, self.height + 1, self.timestamp); // a row more for the cursor, left below
+        for (time, data) in &self.events {
+            text += &format!("[{:.6}, \"o\", {}]\n", time, json_string(data));
+        }
+        text
+    }
+    fn record(&mut self) {
+        let data = String::from_utf8_lossy(&std::mem::take(self.ansi.get_mut())).into_owned();
+        let time = match self.clock {
+            Clock::Wall => self.started.elapsed(),
+            Clock::Fixed(frame) => frame * self.events.len() as u32,
+        };
+        self.events.push((time.as_secs_f64(), data));
+    }
+    fn extend(&mut self, y: usize, right: usize) {
+        self.height = self.height.max(y + 1);
+        self.width = self.width.max(right);
+    }
+}
+impl Renderer for Cast {
+    fn clear(&mut self) {
+        self.ansi.clear();
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        self.extend(y, x + 1);
+        self.ansi.draw_cell(y, x, ch, color);
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        self.extend(y, x + text.chars().count());
+        self.ansi.draw_text(y, x, text);
+    }
+    fn flush_frame(&mut self) {
+        self.ansi.flush_frame();
+        self.record();
+    }
+    fn finish(&mut self) {
+        self.ansi.finish();
+        self.record();
+        if let Err(e) = fs::write(&self.path, self.contents()) {
+            eprintln!("Couldn't write {}: {}", self.path, e);
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            ch if (ch as u32) < 0x20 => json += &format!("\\u{:04x}", ch as u32),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[test]
+fn test_cast() {
+    let mut cast = Cast::new("unused.cast", Clock::Fixed(Duration::from_millis(250)));
+    cast.clear();
+    cast.draw_cell(0, 2, '☻', Some(91));
+    cast.flush_frame();
+    cast.draw_text(1, 0, "\"Hi\"");
+    cast.flush_frame();
+    let contents = cast.contents();
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines[0], format!("{{\"version\": 2, \"width\": 4, \"height\": 3, \"timestamp\": {}}}", cast.timestamp));
+    assert_eq!(&lines[1..], [
+        "[0.000000, \"o\", \"\\u001b[2J\\u001b[?25l\\u001b[1;3H\\u001b[91m☻\"]",
+        "[0.250000, \"o\", \"\\u001b[2;1H\\u001b[0m\\\"Hi\\\"\"]",
+    ]);
+}
@@ -0,0 +1,202 @@
+//! Pictures of a grid of characters, each a square of color, saved as PPM or PNG (going by the
+//! file's extension) for documentation and for diffing runs:
+//! ```text
+//! cargo run -- --image beam.png --scale 4 --palette "#=ffd700,.=000040"
+//! cargo run -- --image life.ppm --every-frame     # life_0001.ppm, life_0002.ppm, ... and life.ppm
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::{Renderer, Snapshot};
+
+pub type Rgb = [u8; 3];
+
+/// The color of each character
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Palette {
+    colors: BTreeMap<char, Rgb>,
+    other: Rgb,
+}
+impl Palette {
+    /// Blank and '.' are black, anything else white
+    pub fn new() -> Self {
+        Palette { colors: BTreeMap::new(), other: [255, 255, 255] }
+            .with(' ', [0, 0, 0])
+            .with('.', [0, 0, 0])
+    }
+    pub fn with(mut self, ch: char, rgb: Rgb) -> Self {
+        self.colors.insert(ch, rgb);
+        self
+    }
+    /// The default palette, changed as `spec` says: "#=ffffff,.=303030", hex RGB per character
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut palette = Palette::new();
+        for entry in spec.split(',').filter(|entry| !entry.is_empty()) {
+            let mut chars = entry.chars();
+            let (ch, rgb) = match (chars.next(), chars.next(), chars.as_str()) {
+                (Some(ch), Some('='), hex) if hex.len() == 6 => (ch, hex),
+                _ => return Err(format!("Palette entry '{}' isn't <char>=<rrggbb>", entry)),
+            };
+            let mut color = [0; 3];
+            for (i, byte) in color.iter_mut().enumerate() {
+                *byte = u8::from_str_radix(&rgb[i * 2..i * 2 + 2], 16)
+                    .map_err(|_| format!("Palette entry '{}' has a bad color", entry))?;
+            }
+            palette = palette.with(ch, color);
+        }
+        Ok(palette)
+    }
+    pub fn color(&self, ch: char) -> Rgb {
+        *self.colors.get(&ch).unwrap_or(&self.other)
+    }
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new()
+    }
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Picture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>, // row by row
+}
+impl Picture {
+    /// Each character a `scale` pixel square.  Short rows are padded with blanks.
+    pub fn from_rows(rows: &[Vec<char>], palette: &Palette, scale: usize) -> Self {
+        let scale = scale.max(1);
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let (width, height) = (columns * scale, rows.len() * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in rows {
+            let line: Vec<Rgb> = (0..columns)
+                .map(|x| palette.color(*row.get(x).unwrap_or(&' ')))
+                .flat_map(|rgb| std::iter::repeat_n(rgb, scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        Picture { width, height, pixels }
+    }
+    pub fn from_text(text: &str, palette: &Palette, scale: usize) -> Self {
+        let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        Picture::from_rows(&rows, palette, scale)
+    }
+    pub fn ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.extend(self.pixels.iter().flatten());
+        data
+    }
+    pub fn png(&self) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::new();
+        header.extend(&(self.width as u32).to_be_bytes());
+        header.extend(&(self.height as u32).to_be_bytes());
+        header.extend(&[8, 2, 0, 0, 0]); // 8 bit RGB, no interlacing
+        chunk(&mut data, b"IHDR", &header);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.width.max(1)) {
+            encoder.write_all(&[0]).unwrap(); // no filter
+            encoder.write_all(&row.concat()).unwrap();
+        }
+        chunk(&mut data, b"IDAT", &encoder.finish().unwrap());
+        chunk(&mut data, b"IEND", &[]);
+        data
+    }
+    /// PNG if `path` ends .png, PPM otherwise
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let is_png = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        fs::write(path, if is_png {self.png()} else {self.ppm()})
+    }
+}
+fn chunk(data: &mut Vec<u8>, kind: &[u8; 4], contents: &[u8]) {
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(contents);
+    data.extend(&(contents.len() as u32).to_be_bytes());
+    data.extend(kind);
+    data.extend(contents);
+    data.extend(&crc.sum().to_be_bytes());
+}
+/// "life.png" numbered: "life_0001.png"
+fn numbered(path: &str, n: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{:04}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{:04}", stem, n),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Keeps the screen, saving it as a picture once drawing finishes, and at every frame if asked
+/// (up to `frames` of them, as many as are animated)
+pub struct Export {
+    screen: Snapshot,
+    path: String,
+    palette: Palette,
+    scale: usize,
+    every_frame: bool,
+    frames: Option<usize>,
+}
+impl Export {
+    pub fn new(path: &str, palette: Palette, scale: usize, every_frame: bool, frames: Option<usize>) -> Self {
+        Export { screen: Snapshot::new(), path: path.to_string(), palette, scale, every_frame, frames }
+    }
+    fn save(&self, path: &str) {
+        let picture = Picture::from_rows(self.screen.rows(), &self.palette, self.scale);
+        if let Err(e) = picture.save(path) {
+            eprintln!("Couldn't write {}: {}", path, e);
+        }
+    }
+}
+impl Renderer for Export {
+    fn clear(&mut self) {
+        self.screen.clear();
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        self.screen.draw_cell(y, x, ch, color);
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        self.screen.draw_text(y, x, text);
+    }
+    fn flush_frame(&mut self) {
+        self.screen.flush_frame();
+        if self.every_frame && self.frames.is_none_or(|frames| self.screen.frames() <= frames) {
+            self.save(&numbered(&self.path, self.screen.frames()));
+        }
+    }
+    fn finish(&mut self) {
+        self.save(&self.path);
+    }
+}
+
+#[test]
+fn test_image() {
+    use std::io::Read;
+    let palette = Palette::parse("#=ff8000").unwrap();
+    assert!(Palette::parse("#=ff80").is_err());
+    let picture = Picture::from_text("#.\n#", &palette, 2);
+    let (orange, black) = ([255, 128, 0], [0, 0, 0]);
+    assert_eq!(picture.pixels, [orange, orange, black, black, orange, orange, black, black,
+                                orange, orange, black, black, orange, orange, black, black]);
+    assert_eq!(&picture.ppm()[..11], b"P6\n4 4\n255\n");
+
+    let png = picture.png();
+    assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+    assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    let idat_len = u32::from_be_bytes([png[33], png[34], png[35], png[36]]) as usize;
+    let mut rows = Vec::new();
+    flate2::read::ZlibDecoder::new(&png[41..41 + idat_len]).read_to_end(&mut rows).unwrap();
+    assert_eq!(rows.len(), 4 * (1 + 4 * 3));
+    assert_eq!(&rows[..7], &[0, 255, 128, 0, 255, 128, 0]);
+
+    assert_eq!(numbered("out/life.png", 7), "out/life_0007.png");
+}
@@ -0,0 +1,126 @@
+//! Reading the block letters some puzzles answer with (day08's image, day11's registration
+//! identifier): the `#`/blank art is cut into glyphs at its blank columns, and each glyph looked
+//! up in a `Font`, so an answer can be a `String` a test asserts on.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The usual 6 row letters.  Ones not seen yet can be added with `Font::with`.
+const STANDARD: [(char, &str); 18] = [
+    ('A', ".##.|#..#|#..#|####|#..#|#..#"),
+    ('B', "###.|#..#|###.|#..#|#..#|###."),
+    ('C', ".##.|#..#|#...|#...|#..#|.##."),
+    ('E', "####|#...|###.|#...|#...|####"),
+    ('F', "####|#...|###.|#...|#...|#..."),
+    ('G', ".##.|#..#|#...|#.##|#..#|.###"),
+    ('H', "#..#|#..#|####|#..#|#..#|#..#"),
+    ('I', "###|.#.|.#.|.#.|.#.|###"),
+    ('J', "..##|...#|...#|...#|#..#|.##."),
+    ('K', "#..#|#.#.|##..|#.#.|#.#.|#..#"),
+    ('L', "#...|#...|#...|#...|#...|####"),
+    ('O', ".##.|#..#|#..#|#..#|#..#|.##."),
+    ('P', "###.|#..#|#..#|###.|#...|#..."),
+    ('R', "###.|#..#|#..#|###.|#.#.|#..#"),
+    ('S', ".###|#...|#...|.##.|...#|###."),
+    ('U', "#..#|#..#|#..#|#..#|#..#|.##."),
+    ('Y', "#...#|#...#|.#.#.|..#..|..#..|..#.."),
+    ('Z', "####|...#|..#.|.#..|#...|####"),
+];
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum ReadError {
+    Height { rows: usize, expected: usize }, // the inked rows don't match the font
+    Unrecognized { columns: Vec<usize>, read: String }, // where each unknown glyph starts; the rest read, '?' for those
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Height { rows, expected } =>
+                write!(f, "Letters are {} rows high, not {}", expected, rows),
+            ReadError::Unrecognized { columns, read } =>
+                write!(f, "Read \"{}\", but glyphs at columns {:?} aren't in the font", read, columns),
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Font {
+    height: usize,
+    glyphs: BTreeMap<Vec<Vec<bool>>, char>,
+}
+impl Font {
+    /// The standard 4x6 letters
+    pub fn standard() -> Self {
+        STANDARD.iter().fold(Font { height: 6, glyphs: BTreeMap::new() }, |font, (ch, art)| font.with(*ch, art))
+    }
+    /// `art` is the glyph's rows separated by '|' or newlines, '#' for ink, anything else blank.
+    /// Glyphs are told apart by the blank columns between them, so one mustn't have any inside.
+    pub fn with(mut self, ch: char, art: &str) -> Self {
+        let rows: Vec<Vec<char>> = art.split(['|', '\n']).map(|row| row.chars().collect()).collect();
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let glyph = (0..columns).map(|x| column(&rows, x)).filter(|inked| inked.contains(&true)).collect();
+        self.glyphs.insert(glyph, ch);
+        self
+    }
+    /// The letters in `text`, left to right, ignoring any blank margin
+    pub fn read(&self, text: &str) -> Result<String, ReadError> {
+        let mut rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        while rows.last().is_some_and(|row| !row.iter().any(|ch| ink(*ch))) {
+            rows.pop();
+        }
+        let top = rows.iter().take_while(|row| !row.iter().any(|ch| ink(*ch))).count();
+        let rows = &rows[top..];
+        if rows.len() != self.height {
+            return Err(ReadError::Height { rows: rows.len(), expected: self.height });
+        }
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut read = String::new();
+        let mut unknown = Vec::new();
+        let mut glyph: Vec<Vec<bool>> = Vec::new();
+        for x in 0..=width {
+            let inked = column(rows, x);
+            if inked.iter().any(|ink| *ink) {
+                glyph.push(inked);
+            } else if !glyph.is_empty() {
+                match self.glyphs.get(&glyph) {
+                    Some(ch) => read.push(*ch),
+                    None => {
+                        unknown.push(x - glyph.len());
+                        read.push('?');
+                    },
+                }
+                glyph.clear();
+            }
+        }
+        if unknown.is_empty() {
+            Ok(read)
+        } else {
+            Err(ReadError::Unrecognized { columns: unknown, read })
+        }
+    }
+}
+impl Default for Font {
+    fn default() -> Self {
+        Font::standard()
+    }
+}
+fn ink(ch: char) -> bool {
+    ch == '#'
+}
+fn column(rows: &[Vec<char>], x: usize) -> Vec<bool> {
+    rows.iter().map(|row| row.get(x).is_some_and(|ch| ink(*ch))).collect()
+}
+
+#[test]
+fn test_letters() {
+    let day08 = "\n#  # ####  ##  #### #  # \n#  #    # #  #    # #  # \n####   #  #      #  #  # \n\
+                 #  #  #   #     #   #  # \n#  # #    #  # #    #  # \n#  # ####  ##  ####  ##  \n";
+    assert_eq!(Font::standard().read(day08), Ok("HZCZU".to_string()));
+    let t = ["###", " # ", " # ", " # ", " # ", " # "];
+    let with_t: String = day08.lines().skip(1).zip(&t).map(|(row, t)| format!("{}{}\n", row, t)).collect();
+    assert_eq!(Font::standard().read(&with_t),
+        Err(ReadError::Unrecognized { columns: vec![25], read: "HZCZU?".to_string() }));
+    let font = Font::standard().with('T', "###.|.#..|.#..|.#..|.#..|.#..");
+    assert_eq!(font.read(&with_t), Ok("HZCZUT".to_string()));
+    assert_eq!(font.read("#\n#"), Err(ReadError::Height { rows: 2, expected: 6 }));
+}
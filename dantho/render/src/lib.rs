@@ -0,0 +1,95 @@
+//! Shared drawing for the animated days -- the print/set_cursor_pos/set_color helpers each of
+//! day13..day25 carried, behind a `Renderer` so a day's state doesn't care where it is drawn:
+//! an ANSI terminal, a plain-text snapshot, or nowhere at all (CI, tests, piped output).
+
+pub mod ansi;
+pub mod snapshot;
+pub mod pace;
+pub mod cast;
+pub mod image;
+pub mod letters;
+
+pub use ansi::Ansi;
+pub use snapshot::Snapshot;
+pub use pace::{Pace, RunConfig};
+pub use cast::Cast;
+pub use image::{Export, Palette, Picture};
+pub use letters::{Font, ReadError};
+
+pub trait Renderer: Send {
+    /// Blank the screen, to start drawing afresh
+    fn clear(&mut self);
+    /// `ch` at row `y`, column `x`, in an ANSI color (an SGR code, e.g. 91 for bright red) or
+    /// the default color if None
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>);
+    /// `text` from row `y`, column `x` rightward, in the default color
+    fn draw_text(&mut self, y: usize, x: usize, text: &str);
+    /// Show everything drawn since the last flush
+    fn flush_frame(&mut self);
+    /// Drawing is over: leave what follows (the answers) below the picture rather than on it
+    fn finish(&mut self) {}
+}
+
+impl<R: Renderer + ?Sized> Renderer for Box<R> {
+    fn clear(&mut self) {
+        (**self).clear();
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        (**self).draw_cell(y, x, ch, color);
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        (**self).draw_text(y, x, text);
+    }
+    fn flush_frame(&mut self) {
+        (**self).flush_frame();
+    }
+    fn finish(&mut self) {
+        (**self).finish();
+    }
+}
+/// Draws on both: the terminal and a recording, say
+pub struct Tee<A, B>(pub A, pub B);
+impl<A: Renderer, B: Renderer> Renderer for Tee<A, B> {
+    fn clear(&mut self) {
+        self.0.clear();
+        self.1.clear();
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        self.0.draw_cell(y, x, ch, color);
+        self.1.draw_cell(y, x, ch, color);
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        self.0.draw_text(y, x, text);
+        self.1.draw_text(y, x, text);
+    }
+    fn flush_frame(&mut self) {
+        self.0.flush_frame();
+        self.1.flush_frame();
+    }
+    fn finish(&mut self) {
+        self.0.finish();
+        self.1.finish();
+    }
+}
+
+/// Draws nothing, only counting frames
+#[derive(Debug,Default)]
+pub struct Headless {
+    frames: usize,
+}
+impl Headless {
+    pub fn new() -> Self {
+        Headless::default()
+    }
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+impl Renderer for Headless {
+    fn clear(&mut self) {}
+    fn draw_cell(&mut self, _y: usize, _x: usize, _ch: char, _color: Option<u8>) {}
+    fn draw_text(&mut self, _y: usize, _x: usize, _text: &str) {}
+    fn flush_frame(&mut self) {
+        self.frames += 1;
+    }
+}
@@ -0,0 +1,265 @@
+//! How fast an animated day runs, read from its command line so every day takes the same options:
+//! ```text
+//! cargo run                 # realtime: the day's own delay between frames
+//! cargo run -- --fps 30     # a fixed frame rate
+//! cargo run -- --enter      # a frame per press of Enter (stdin comes a line at a time, not a key)
+//! cargo run -- --headless   # draw nothing; just the answers, as fast as they can be computed
+//! cargo run -- --frames 50  # animate the first 50 frames, then finish headless
+//! cargo run -- --cast x.cast  # and record them too (see cast.rs)
+//! cargo run -- --image x.png  # and save the final picture (see image.rs for its options)
+//! ```
+//! Output that isn't a terminal is always headless.  A headless recording is stamped with the
+//! day's own delay between frames (a 30th of a second, if that's 0).
+
+use std::convert::TryFrom;
+use std::io::{stdin, stdout, IsTerminal};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use crate::{Ansi, Cast, Export, Headless, Palette, Picture, Renderer, Tee};
+use crate::cast::Clock;
+
+const USAGE: &str = "Options: [--headless | --realtime | --fps <N> | --enter] [--frames <N>] [--cast <file.cast>]
+         [--image <file.png|.ppm> [--every-frame] [--scale <N>] [--palette <spec>]]";
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Pace {
+    Headless,
+    Realtime,
+    Fps(u32),
+    Enter,
+}
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct RunConfig {
+    pub pace: Pace,
+    pub frames: Option<usize>, // frames to animate before going headless
+    pub cast: Option<String>,  // the file to record to
+    pub image: Option<String>, // the file to save the final picture to
+    pub every_frame: bool,     // and every frame before it, numbered
+    pub scale: usize,          // pixels per character
+    pub palette: Palette,
+}
+impl RunConfig {
+    /// The options as given to this program.  A bad one is a usage error: reported, and the run ends.
+    pub fn from_args() -> Self {
+        RunConfig::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        })
+    }
+    /// The options above, ignoring any others (a day's own, like day13's --cheat)
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut config = RunConfig {
+            pace: Pace::Realtime,
+            frames: None,
+            cast: None,
+            image: None,
+            every_frame: false,
+            scale: 4,
+            palette: Palette::new(),
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| args.next().ok_or_else(|| format!("{} needs a value", option));
+            let mut number = |option: &str| -> Result<usize, String> {
+                let value = value(option)?;
+                value.parse().map_err(|_| format!("{} needs a number, not '{}'", option, value))
+            };
+            match arg.as_str() {
+                "--headless" => config.pace = Pace::Headless,
+                "--realtime" => config.pace = Pace::Realtime,
+                "--enter" => config.pace = Pace::Enter,
+                "--fps" => {
+                    let fps = number("--fps")?.max(1);
+                    config.pace = Pace::Fps(u32::try_from(fps).map_err(|_| format!("--fps {} is too fast", fps))?);
+                },
+                "--frames" => config.frames = Some(number("--frames")?),
+                "--cast" => config.cast = Some(value("--cast")?),
+                "--image" => config.image = Some(value("--image")?),
+                "--every-frame" => config.every_frame = true,
+                "--scale" => config.scale = number("--scale")?.max(1),
+                "--palette" => config.palette = Palette::parse(&value("--palette")?)?,
+                _ => (),
+            }
+        }
+        if !stdout().is_terminal() {
+            config.pace = Pace::Headless;
+        }
+        Ok(config)
+    }
+    /// The options for one of several runs in a program, its files named for the run (x.cast becomes
+    /// x-dfs.cast for run "dfs") so that no run overwrites another's
+    pub fn for_run(&self, run: &str) -> RunConfig {
+        let named = |path: &String| {
+            let path = Path::new(path);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let name = match path.extension() {
+                Some(ext) => format!("{}-{}.{}", stem, run, ext.to_string_lossy()),
+                None => format!("{}-{}", stem, run),
+            };
+            path.with_file_name(name).to_string_lossy().into_owned()
+        };
+        RunConfig { cast: self.cast.as_ref().map(named), image: self.image.as_ref().map(named), ..self.clone() }
+    }
+    /// Waits between frames, `realtime` being the day's own delay
+    pub fn pacer(&self, realtime: Duration) -> Pacer {
+        Pacer { pace: self.pace, realtime, frames_left: self.frames, last: None }
+    }
+    /// The terminal, paced, or nothing at all if headless; and the recording, if there is one
+    pub fn renderer(&self, realtime: Duration) -> Box<dyn Renderer> {
+        let display: Box<dyn Renderer> = match self.pace {
+            Pace::Headless => Box::new(Headless::new()),
+            _ => Box::new(Ansi::new()),
+        };
+        let screen: Box<dyn Renderer> = match &self.cast {
+            Some(path) => {
+                let clock = match self.pace {
+                    Pace::Headless if realtime == Duration::from_millis(0) => Clock::Fixed(Duration::from_secs(1) / 30),
+                    Pace::Headless => Clock::Fixed(realtime),
+                    _ => Clock::Wall,
+                };
+                Box::new(Tee(display, Cast::new(path, clock)))
+            },
+            None => display,
+        };
+        let screen: Box<dyn Renderer> = Box::new(Paced::new(screen, self.pacer(realtime)));
+        // Outside the pacing, so the picture is of the final state even when --frames stops the animation
+        // short; though --every-frame saves only the frames animated
+        match &self.image {
+            Some(path) => Box::new(Tee(screen, Export::new(path, self.palette.clone(), self.scale, self.every_frame, self.frames))),
+            None => screen,
+        }
+    }
+    /// For days that print a grid rather than drawing it: save it as --image says, if it says to
+    pub fn save_image(&self, text: &str) {
+        if let Some(path) = &self.image {
+            if let Err(e) = Picture::from_text(text, &self.palette, self.scale).save(path) {
+                eprintln!("Couldn't write {}: {}", path, e);
+            }
+        }
+    }
+}
+
+pub struct Pacer {
+    pace: Pace,
+    realtime: Duration,
+    frames_left: Option<usize>,
+    last: Option<Instant>, // when the last frame was shown, for Fps
+}
+impl Pacer {
+    /// Hold the frame just shown for as long as the pace says.  False once the frames to animate
+    /// have run out, after which there's no more waiting.
+    pub fn pause(&mut self) -> bool {
+        match &mut self.frames_left {
+            Some(0) => return false,
+            Some(n) => *n -= 1,
+            None => (),
+        }
+        match self.pace {
+            Pace::Headless => (),
+            Pace::Realtime => sleep(self.realtime),
+            Pace::Fps(fps) => {
+                let frame = Duration::from_secs(1) / fps;
+                if let Some(last) = self.last {
+                    sleep(frame.saturating_sub(last.elapsed()));
+                }
+                self.last = Some(Instant::now());
+            },
+            Pace::Enter => {
+                let mut line = String::new();
+                stdin().read_line(&mut line).ok();
+            },
+        }
+        self.frames_left != Some(0)
+    }
+}
+
+/// Another renderer, paced at each frame, that stops drawing when the pacer runs out of frames
+pub struct Paced<R> {
+    inner: R,
+    pacer: Pacer,
+    drawing: bool,
+}
+impl<R: Renderer> Paced<R> {
+    pub fn new(inner: R, pacer: Pacer) -> Self {
+        Paced { inner, pacer, drawing: true }
+    }
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+impl<R: Renderer> Renderer for Paced<R> {
+    fn clear(&mut self) {
+        if self.drawing {
+            self.inner.clear();
+        }
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, color: Option<u8>) {
+        if self.drawing {
+            self.inner.draw_cell(y, x, ch, color);
+        }
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        if self.drawing {
+            self.inner.draw_text(y, x, text);
+        }
+    }
+    fn flush_frame(&mut self) {
+        if self.drawing {
+            self.inner.flush_frame();
+            self.drawing = self.pacer.pause();
+        }
+    }
+    fn finish(&mut self) {
+        self.inner.finish();
+    }
+}
+
+#[test]
+fn test_pace() {
+    let args = |line: &str| line.split_whitespace().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let config = RunConfig { pace: Pace::Realtime, frames: None, ..RunConfig::parse(Vec::new()).unwrap() };
+    assert_eq!(RunConfig::parse(args("--cheat --fps 1000 --frames 2")).unwrap().frames, Some(2));
+    assert_eq!(RunConfig::parse(args("--fps fast")), Err("--fps needs a number, not 'fast'".to_string()));
+    assert_eq!(RunConfig::parse(args("--frames")), Err("--frames needs a value".to_string()));
+    assert_eq!(RunConfig::parse(args("--fps 4294967296")), Err("--fps 4294967296 is too fast".to_string()));
+    assert!(RunConfig::parse(args("--palette nonsense")).is_err());
+    let named = RunConfig::parse(args("--cast out/x.cast --image x")).unwrap().for_run("part1");
+    assert_eq!((named.cast.as_deref(), named.image.as_deref()), (Some("out/x-part1.cast"), Some("x-part1")));
+
+    // Only the first two frames are drawn
+    let config = RunConfig { frames: Some(2), ..config };
+    let mut paced = Paced::new(crate::Snapshot::new(), config.pacer(Duration::from_millis(1)));
+    for (x, ch) in "abc".chars().enumerate() {
+        paced.draw_cell(0, x, ch, None);
+        paced.flush_frame();
+    }
+    assert_eq!(paced.inner().text(), "ab\n");
+    assert_eq!(paced.inner().frames(), 2);
+
+    // But the picture is of everything drawn
+    let path = std::env::temp_dir().join(format!("pace_test_{}.ppm", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let config = RunConfig { pace: Pace::Headless, frames: Some(1), image: Some(path.clone()), scale: 1, ..config };
+    let mut screen = config.renderer(Duration::from_millis(1));
+    for (x, ch) in "abc".chars().enumerate() {
+        screen.draw_cell(0, x, ch, None);
+        screen.flush_frame();
+    }
+    screen.finish();
+    let saved = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(&saved[..11], b"P6\n3 1\n255\n");
+
+    // And --every-frame saves only the frames animated
+    let config = RunConfig { every_frame: true, ..config };
+    let mut screen = config.renderer(Duration::from_millis(1));
+    for (x, ch) in "abc".chars().enumerate() {
+        screen.draw_cell(0, x, ch, None);
+        screen.flush_frame();
+    }
+    screen.finish();
+    let numbered = |n: usize| path.replace(".ppm", &format!("_{:04}.ppm", n));
+    let saved = [numbered(1), numbered(2), path.clone()].map(|path| std::fs::remove_file(path).is_ok());
+    assert_eq!(saved, [true, false, true]);
+}
@@ -0,0 +1,65 @@
+//! Plain text: the screen kept as a grid of characters, for tests and for logs
+
+use crate::Renderer;
+
+#[derive(Debug,Default)]
+pub struct Snapshot {
+    rows: Vec<Vec<char>>,
+    frames: usize,
+}
+impl Snapshot {
+    pub fn new() -> Self {
+        Snapshot::default()
+    }
+    /// Frames flushed so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+    /// The screen as it stands, a line per row, without trailing blanks.  Colors are dropped.
+    pub fn text(&self) -> String {
+        self.rows.iter()
+            .map(|row| format!("{}\n", row.iter().collect::<String>().trim_end()))
+            .collect()
+    }
+}
+impl Renderer for Snapshot {
+    fn clear(&mut self) {
+        self.rows.clear();
+    }
+    fn draw_cell(&mut self, y: usize, x: usize, ch: char, _color: Option<u8>) {
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, ' ');
+        }
+        row[x] = ch;
+    }
+    fn draw_text(&mut self, y: usize, x: usize, text: &str) {
+        for (i, ch) in text.chars().enumerate() {
+            self.draw_cell(y, x + i, ch, None);
+        }
+    }
+    fn flush_frame(&mut self) {
+        self.frames += 1;
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    let mut screen = Snapshot::new();
+    screen.draw_text(1, 2, "Score: 7");
+    screen.draw_cell(0, 0, '#', Some(91));
+    screen.flush_frame();
+    assert_eq!(screen.text(), "#\n  Score: 7\n");
+    screen.draw_cell(1, 9, '9', None);
+    screen.clear();
+    screen.draw_cell(0, 1, '@', None);
+    screen.flush_frame();
+    assert_eq!(screen.text(), " @\n");
+    assert_eq!(screen.frames(), 2);
+}